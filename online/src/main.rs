@@ -0,0 +1,35 @@
+use std::env;
+use std::io;
+
+use solutions::cli;
+use solutions::input::{self, InputSource};
+
+// downloads inputs, caching them through rudolf-rs
+struct Online {
+    client: rudolf_rs::Client,
+}
+
+impl Online {
+    fn new(session_key: String) -> Self {
+        Self { client: rudolf_rs::Client::new(session_key) }
+    }
+}
+
+impl InputSource for Online {
+    fn get(&self, day: u8) -> io::Result<String> {
+        self.client
+            .get(2022, day.into())
+            .map_err(|err| io::Error::other(format!("{:?}", err)))
+    }
+}
+
+// local files first, then downloads when `AOC_SESSION` is set
+fn main() {
+    let mut source = input::default_source();
+
+    if let Ok(session_key) = env::var("AOC_SESSION") {
+        source.push(Box::new(Online::new(session_key)));
+    }
+
+    cli::run(Box::new(source));
+}
@@ -0,0 +1,156 @@
+// The command line runner, shared by the offline binary in this package and
+// the downloading one in `online/`.
+
+use std::env;
+use std::process;
+
+use crate::input::{self, InputSource};
+use crate::params;
+use crate::{solution, Solution, DAYS};
+
+const USAGE: &str = "usage: aoc2022 [--input <file|->] [--set <name=value>]... <day> [part]\n       \
+                     aoc2022 all";
+
+fn parts(solution: &dyn Solution) -> Vec<(u8, u8)> {
+    let day = solution.day();
+
+    if solution.has_part2() {
+        vec![(day, 1), (day, 2)]
+    } else {
+        vec![(day, 1)]
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Vec<(u8, u8)>, String> {
+    match args {
+        [all] if all == "all" => {
+            Ok(DAYS.iter().flat_map(|day| parts(day)).collect())
+        },
+        [day] => Ok(parts(parse_day(day)?)),
+        [day, part] => {
+            let solution = parse_day(day)?;
+            let day = solution.day();
+            let part = match part.parse::<u8>() {
+                Ok(part @ 1..=2) => part,
+                _ => return Err(format!("invalid part: {}\n{}", part, USAGE)),
+            };
+
+            if !parts(solution).contains(&(day, part)) {
+                return Err(format!("day {} has no part {}", day, part));
+            }
+
+            Ok(vec![(day, part)])
+        },
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn parse_day(arg: &str) -> Result<&'static dyn Solution, String> {
+    arg
+        .parse::<u8>()
+        .ok()
+        .and_then(solution)
+        .ok_or_else(|| format!("no solution for day {}\n{}", arg, USAGE))
+}
+
+// `--input -` reads stdin, `--input <file>` a single file, otherwise inputs
+// come from the default source
+fn parse_source(args: &mut Vec<String>) -> Result<Option<Box<dyn InputSource>>, String> {
+    let pos = match args.iter().position(|arg| arg == "--input") {
+        Some(pos) => pos,
+        None => return Ok(None),
+    };
+
+    if pos + 1 >= args.len() {
+        return Err(USAGE.to_string());
+    }
+
+    let path = args.remove(pos + 1);
+    args.remove(pos);
+
+    if path == "-" {
+        Ok(Some(Box::new(input::Stdin)))
+    } else {
+        Ok(Some(Box::new(input::File::new(path))))
+    }
+}
+
+// every `--set <name=value>`, overriding the day's parameters such as
+// `preset=example` or day 15's `row=10`
+fn parse_overrides(args: &mut Vec<String>) -> Result<Vec<(String, String)>, String> {
+    let mut overrides = vec![];
+
+    while let Some(pos) = args.iter().position(|arg| arg == "--set") {
+        if pos + 1 >= args.len() {
+            return Err(USAGE.to_string());
+        }
+
+        let arg = args.remove(pos + 1);
+        args.remove(pos);
+
+        let assignment = params::parse_override(&arg)
+            .ok_or_else(|| format!("invalid parameter: {}\n{}", arg, USAGE))?;
+
+        overrides.push(assignment);
+    }
+
+    Ok(overrides)
+}
+
+// runs the puzzles named on the command line, reading inputs from
+// `default_source` unless `--input` is given
+pub fn run(default_source: Box<dyn InputSource>) {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+
+    let (source, overrides, puzzles) = parse_source(&mut args)
+        .and_then(|source| Ok((source, parse_overrides(&mut args)?)))
+        .and_then(|(source, overrides)| {
+            let puzzles = parse_args(&args)?;
+
+            // an input holds a single day's puzzle
+            let source = match source {
+                Some(_) if args == ["all"] => return Err(USAGE.to_string()),
+                Some(source) => source,
+                None => default_source,
+            };
+
+            Ok((source, overrides, puzzles))
+        })
+        .unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(2);
+        });
+
+    let mut inputs: Vec<(u8, String)> = Vec::new();
+
+    for (day, part) in puzzles {
+        // fetch each day once, as stdin can only be read once
+        let input = match inputs.iter().find(|(d, _)| *d == day) {
+            Some((_, input)) => input,
+            None => {
+                let input = source.get(day).unwrap_or_else(|err| {
+                    eprintln!("no input for day {}: {}", day, err);
+                    process::exit(1);
+                });
+
+                inputs.push((day, input));
+                &inputs.last().unwrap().1
+            },
+        };
+
+        let solution = solution(day).unwrap();
+        let answer = solution.try_part_with(part, input, &overrides);
+
+        match answer {
+            Ok(Some(answer)) => println!("day {:02} part {}: {}", day, part, answer),
+            Ok(None) => {
+                eprintln!("day {} has no part {}", day, part);
+                process::exit(1);
+            },
+            Err(err) => {
+                eprintln!("{}", err);
+                process::exit(1);
+            },
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// Cycle detection for simulations that settle into a loop. Each step is
+// recorded with a fingerprint of the state and a value that grows by the same
+// amount every time round the loop, such as the height of a tower. Once a
+// fingerprint repeats, the value at any later step can be extrapolated.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    // the first step of the loop
+    pub start: usize,
+    pub length: usize,
+    // how much the value grows per time round the loop
+    pub delta: i64,
+}
+
+impl Cycle {
+    // the value at step `n`, given the values of at least the steps up to the
+    // end of the first time round the loop
+    pub fn extrapolate(&self, values: &[i64], n: usize) -> i64 {
+        if n < self.start {
+            return values[n];
+        }
+
+        let (loops, offset) = ((n - self.start) / self.length, (n - self.start) % self.length);
+
+        values[self.start + offset] + loops as i64 * self.delta
+    }
+}
+
+pub struct Detector<K> {
+    seen: HashMap<K, usize>,
+    values: Vec<i64>,
+}
+
+impl<K: Eq + Hash> Detector<K> {
+    pub fn new() -> Self {
+        Self { seen: HashMap::new(), values: vec![] }
+    }
+
+    // records the next step, returning the cycle if its state has been seen
+    // before
+    pub fn push(&mut self, key: K, value: i64) -> Option<Cycle> {
+        let step = self.values.len();
+        self.values.push(value);
+
+        let start = *self.seen.entry(key).or_insert(step);
+
+        if start == step {
+            return None;
+        }
+
+        Some(Cycle {
+            start,
+            length: step - start,
+            delta: value - self.values[start],
+        })
+    }
+
+    // every value recorded so far, by step
+    pub fn values(&self) -> &[i64] {
+        &self.values
+    }
+}
+
+impl<K: Eq + Hash> Default for Detector<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cycle::{Cycle, Detector};
+
+    #[test]
+    fn test_detector() {
+        // 2, 5, then 1, 3, 4 over and over, summed up
+        let steps = [2, 5, 1, 3, 4, 1, 3, 4, 1];
+        let mut detector = Detector::new();
+        let mut total = 0;
+
+        let cycle = steps
+            .iter()
+            .find_map(|&n| {
+                total += n;
+                detector.push(n, total)
+            })
+            .unwrap();
+
+        assert_eq!(cycle, Cycle { start: 2, length: 3, delta: 8 });
+        assert_eq!(detector.values(), [2, 7, 8, 11, 15, 16]);
+        assert_eq!(cycle.extrapolate(detector.values(), 1), 7);
+        assert_eq!(cycle.extrapolate(detector.values(), 6), 19);
+        assert_eq!(cycle.extrapolate(detector.values(), 1000), 15 + 332 * 8);
+    }
+}
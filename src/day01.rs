@@ -1,56 +1,71 @@
-pub fn part1(input: &str) -> u64 {
+use crate::error::Error;
+
+const DAY: u8 = 1;
+
+fn calories(input: &str, fruit: &str) -> Result<u64, Error> {
+    fruit
+        .parse::<u64>()
+        .map_err(|_| Error::parse(DAY, input, fruit, "a number of calories"))
+}
+
+pub fn try_part1(input: &str) -> Result<u64, Error> {
     let mut max: u64 = 0;
     let mut cur: u64 = 0;
 
-    input
-        .split('\n')
-        .for_each(|fruit| {
-            if fruit.is_empty() {
-                if cur > max {
-                    max = cur;
-                }
+    for fruit in input.split('\n') {
+        if fruit.is_empty() {
+            if cur > max {
+                max = cur;
+            }
 
-                cur = 0;
+            cur = 0;
 
-            } else {
-                cur += fruit.parse::<u64>().unwrap_or(0);
-            }
-        });
+        } else {
+            cur += calories(input, fruit)?;
+        }
+    }
 
-    max
+    Ok(max)
 }
 
-pub fn part2(input: &str) -> u64 {
+pub fn try_part2(input: &str) -> Result<u64, Error> {
     let mut top = [0u64; 3];
     let mut cur: u64 = 0;
 
-    input
-        .split('\n')
-        .for_each(|fruit| {
-            if fruit.is_empty() {
-                for i in 0..top.len() {
-                    if cur > top[i] {
-                        for j in ((i + 1)..top.len()).rev() {
-                            top[j] = top[j - 1];
-                        }
-
-                        top[i] = cur;
-                        break;
+    for fruit in input.split('\n') {
+        if fruit.is_empty() {
+            for i in 0..top.len() {
+                if cur > top[i] {
+                    for j in ((i + 1)..top.len()).rev() {
+                        top[j] = top[j - 1];
                     }
-                }
 
-                cur = 0;
-            } else {
-                cur += fruit.parse::<u64>().unwrap_or(0);
+                    top[i] = cur;
+                    break;
+                }
             }
-        });
 
-    top.iter().sum()
+            cur = 0;
+        } else {
+            cur += calories(input, fruit)?;
+        }
+    }
+
+    Ok(top.iter().sum())
+}
+
+pub fn part1(input: &str) -> u64 {
+    try_part1(input).unwrap()
+}
+
+pub fn part2(input: &str) -> u64 {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
     use crate::day01;
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "1000\n2000\n3000\n\n4000\n\n5000\n6000\
                               \n\n7000\n8000\n9000\n\n10000\n";
@@ -67,5 +82,18 @@ mod tests {
         assert_eq!(day01::part2(TEST_INPUT), 45000);
     }
 
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            day01::try_part1("1000\nabc\n"),
+            Err(Error::Parse {
+                day: 1,
+                line: 2,
+                column: 1,
+                expected: "a number of calories".to_string()
+            })
+        );
+    }
+
 }
 
@@ -1,43 +1,55 @@
-pub fn part1(input: &str) -> i64 {
-    let bytes = input.as_bytes();
-    let rows: usize = bytes.len() / 4;
+use crate::error::Error;
 
-    let mut score: i64 = 0;
+const DAY: u8 = 2;
 
-    for row in 0..rows {
-        let i = row * 4;
+// each round's two letters, as 1 to 3
+fn rounds(input: &str) -> Result<Vec<(i64, i64)>, Error> {
+    input
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.as_bytes() {
+            [a @ b'A'..=b'C', b' ', b @ b'X'..=b'Z'] => Ok((*a as i64 - 64, *b as i64 - 87)),
+            _ => Err(Error::parse(DAY, input, line, "a round like `A Y`")),
+        })
+        .collect()
+}
+
+pub fn try_part1(input: &str) -> Result<i64, Error> {
+    let mut score: i64 = 0;
 
-        let opponent = bytes[i] as i64 - 64;
-        let player = bytes[i + 2] as i64 - 87;
+    for (opponent, player) in rounds(input)? {
         let round = (player - opponent + 1).rem_euclid(3) * 3; 
 
         score += player + round;
     }
-    score
+    Ok(score)
 }
 
-pub fn part2(input: &str) -> i64 {
-    let bytes = input.as_bytes();
-    let rows: usize = bytes.len() / 4;
-
+pub fn try_part2(input: &str) -> Result<i64, Error> {
     let mut score: i64 = 0;
 
-    for row in 0..rows {
-        let i = row * 4;
-
-        let opponent = bytes[i] as i64 - 64;
-        let round = (bytes[i + 2] as i64 - 87 - 1) * 3;
-        let player = (opponent + bytes[i + 2] as i64 - 87 - 2 - 1)
+    for (opponent, outcome) in rounds(input)? {
+        let round = (outcome - 1) * 3;
+        let player = (opponent + outcome - 2 - 1)
             .rem_euclid(3) + 1; 
 
         score += round + player;
     }
-    score
+    Ok(score)
+}
+
+pub fn part1(input: &str) -> i64 {
+    try_part1(input).unwrap()
+}
+
+pub fn part2(input: &str) -> i64 {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day02::{part1, part2};
+    use crate::day02::{part1, part2, try_part1};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "A Y\nB X\nC Z\n";
 
@@ -50,5 +62,18 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 12);
     }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            try_part1("A Y\nB W\n"),
+            Err(Error::Parse {
+                day: 2,
+                line: 2,
+                column: 1,
+                expected: "a round like `A Y`".to_string()
+            })
+        );
+    }
 }
 
@@ -1,3 +1,7 @@
+use crate::error::{end_of, Error};
+
+const DAY: u8 = 3;
+
 trait Priority {
     fn priority(&self) -> u8;
 }
@@ -12,12 +16,28 @@ impl Priority for u8 {
     }
 }
 
-pub fn part1(input: &str) -> u64 {
+// the non-empty lines, checking that they only hold item letters
+fn sacks(input: &str) -> Result<Vec<&str>, Error> {
     input
         .split('\n')
+        .filter(|sack| !sack.is_empty())
+        .map(|sack| match sack.find(|c: char| !c.is_ascii_alphabetic()) {
+            Some(i) => Err(Error::parse(DAY, input, &sack[i..], "an item letter")),
+            None => Ok(sack),
+        })
+        .collect()
+}
+
+pub fn try_part1(input: &str) -> Result<u64, Error> {
+    sacks(input)?
+        .into_iter()
         .map(|sack| {
-            let front = sack[..sack.len() / 2].as_bytes();
-            let rear = sack[sack.len() / 2..].as_bytes();
+            if sack.len() % 2 != 0 {
+                return Err(Error::parse(DAY, input, sack, "an even number of items"));
+            }
+
+            let front = &sack.as_bytes()[..sack.len() / 2];
+            let rear = &sack.as_bytes()[sack.len() / 2..];
 
             // hash table
             let mut table = [0u8; 256];
@@ -28,22 +48,22 @@ pub fn part1(input: &str) -> u64 {
 
             for c in rear {
                 if table[*c as usize] != 0 {
-                    return c.priority() as u64;
+                    return Ok(c.priority() as u64);
                 }
             }
 
-            0
+            Err(Error::no_solution(DAY, "a rucksack has no item in both compartments"))
         })
         .sum()
 }
 
-pub fn part2(input: &str) -> u64 {
-    input
-        .trim()
-        .split('\n')
-        .collect::<Vec<&str>>()
+pub fn try_part2(input: &str) -> Result<u64, Error> {
+    sacks(input)?
         .chunks(3)
         .map(|group| {
+            if group.len() < 3 {
+                return Err(Error::parse(DAY, input, end_of(input), "groups of three rucksacks"));
+            }
 
             // hash tables
             let mut tables = [[0u8; 256]; 2];
@@ -54,20 +74,29 @@ pub fn part2(input: &str) -> u64 {
                 }
             }
 
-            for c in group.get(2).unwrap().as_bytes() {
+            for c in group[2].as_bytes() {
                 if tables[0][*c as usize] != 0 && tables[1][*c as usize] != 0 {
-                    return c.priority() as u64;
+                    return Ok(c.priority() as u64);
                 }
             }
 
-            0
+            Err(Error::no_solution(DAY, "a group has no badge"))
         })
         .sum()
 }
 
+pub fn part1(input: &str) -> u64 {
+    try_part1(input).unwrap()
+}
+
+pub fn part2(input: &str) -> u64 {
+    try_part2(input).unwrap()
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::day03::{part1, part2};
+    use crate::day03::{part1, part2, try_part1, try_part2};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "vJrwpWtwJgWrhcsFMMfFFhFp\n\
                               jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL\n\
@@ -85,5 +114,20 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 70);
     }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            try_part1("vJrwpWtwJgWrhcsFMMfFFhFp\nabc1\n"),
+            Err(Error::Parse {
+                day: 3,
+                line: 2,
+                column: 4,
+                expected: "an item letter".to_string()
+            })
+        );
+        assert!(try_part1("abc\n").is_err());
+        assert!(try_part2("vJrwpWtwJgWrhcsFMMfFFhFp\n").is_err());
+    }
 }
 
@@ -1,39 +1,69 @@
-pub fn part1(input: &str) -> u64 {
+use crate::error::Error;
+
+const DAY: u8 = 4;
+
+type Range = (u64, u64);
+
+// `range` is a slice of `input`
+fn range(input: &str, range: &str) -> Result<Range, Error> {
+    let (start, end) = range
+        .split_once('-')
+        .ok_or_else(|| Error::parse(DAY, input, range, "a range like `2-4`"))?;
+
+    let section = |s: &str| {
+        s.parse::<u64>().map_err(|_| Error::parse(DAY, input, s, "a section number"))
+    };
+
+    Ok((section(start)?, section(end)?))
+}
+
+fn pairs(input: &str) -> Result<Vec<(Range, Range)>, Error> {
     input
-        .trim()
-        .split(['\n', '-', ','])
-        .map(|num| num.parse::<u64>().unwrap())
-        .collect::<Vec<u64>>()
-        .chunks(4)
-        .filter(|row| {
-            let (l, r) = ((row[0], row[1]), (row[2], row[3]));
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (l, r) = line
+                .split_once(',')
+                .ok_or_else(|| Error::parse(DAY, input, line, "a pair of ranges"))?;
 
-            (l.0 >= r.0 && l.1 <= r.1) || (r.0 >= l.0 && r.1 <= l.1)
+            Ok((range(input, l)?, range(input, r)?))
         })
-        .count() as u64
+        .collect()
 }
 
-pub fn part2(input: &str) -> u64 {
-    input
-        .trim()
-        .split(['\n', '-', ','])
-        .map(|num| num.parse::<u64>().unwrap())
-        .collect::<Vec<u64>>()
-        .chunks(4)
-        .filter(|row| {
-            let (l, r) = ((row[0], row[1]), (row[2], row[3]));
+pub fn try_part1(input: &str) -> Result<u64, Error> {
+    Ok(pairs(input)?
+        .into_iter()
+        .filter(|(l, r)| {
+            (l.0 >= r.0 && l.1 <= r.1) || (r.0 >= l.0 && r.1 <= l.1)
+        })
+        .count() as u64)
+}
 
+pub fn try_part2(input: &str) -> Result<u64, Error> {
+    Ok(pairs(input)?
+        .into_iter()
+        .filter(|(l, r)| {
             (l.0 >= r.0 && l.0 <= r.1)
             || (r.0 >= l.0 && r.0 <= l.1)
             || (l.1 <= r.1 && l.1 >= r.0)
             || (r.1 <= l.1 && r.1 >= l.0)
         })
-        .count() as u64
+        .count() as u64)
+}
+
+pub fn part1(input: &str) -> u64 {
+    try_part1(input).unwrap()
+}
+
+pub fn part2(input: &str) -> u64 {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day04::{part1, part2};
+    use crate::day04::{part1, part2, try_part1};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "2-4,6-8\n2-3,4-5\n5-7,7-9\n\
                               2-8,3-7\n6-6,4-6\n2-6,4-8\n";
@@ -47,5 +77,18 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 4);
     }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            try_part1("2-4,6-8\n2-3,4-x\n"),
+            Err(Error::Parse {
+                day: 4,
+                line: 2,
+                column: 7,
+                expected: "a section number".to_string()
+            })
+        );
+    }
 }
 
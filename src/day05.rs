@@ -1,114 +1,244 @@
-pub fn part1(input: &str) -> String {
-    let (stacks_s, movs_s) = input
-        .split_once("\n\n")
-        .unwrap();
+use std::fmt;
 
-    // create stacks
-    let stack_count = stacks_s.find('\n').unwrap() / 4 + 1;
-    let mut stacks: Vec<Vec<u8>> = vec![Vec::new(); stack_count];
+use crate::error::{end_of, Error};
 
-    // populate stacks
-    stacks_s
-        .split('\n')
-        .rev()
-        .skip(1)
-        .for_each(|row_s| {
-            let row = row_s.as_bytes();
+const DAY: u8 = 5;
 
-            for i in 0..stack_count {
-                let c = row[i * 4 + 1];
+// `move 1 from 2 to 1`, with stacks counted from 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub count: usize,
+    pub from: usize,
+    pub to: usize,
+}
 
-                if c != b' ' {
-                    stacks[i].push(c);
-                }
-            }
-        });
-
-    // execute moves
-    movs_s
-        .trim()
-        .split([' ', '\n'])
-        .collect::<Vec<_>>()
-        .chunks(6)
-        .for_each(|instr| {
-            let (count, src, dst) = (
-                instr.get(1).unwrap().parse::<usize>().unwrap(),
-                instr.get(3).unwrap().parse::<usize>().unwrap() - 1,
-                instr.get(5).unwrap().parse::<usize>().unwrap() - 1,
-            );
-
-            for _ in 0..count {
-                let container = stacks[src].pop().unwrap();
-                stacks[dst].push(container);
-            }
+impl Move {
+    // `line` is a slice of `input`
+    pub fn parse(input: &str, line: &str) -> Result<Self, Error> {
+        let words: Vec<&str> = line.split(' ').collect();
 
-        });
+        let (count, from, to) = match words[..] {
+            ["move", count, "from", from, "to", to] => (count, from, to),
+            _ => return Err(Error::parse(DAY, input, line, "`move N from A to B`")),
+        };
 
-    let mut top = String::new(); 
-    for stack in stacks {
-        top.push(*stack.last().unwrap() as char);
+        let count = count
+            .parse::<usize>()
+            .map_err(|_| Error::parse(DAY, input, count, "a number of crates"))?;
+
+        let stack = |word: &str| match word.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(n - 1),
+            _ => Err(Error::parse(DAY, input, word, "a stack number")),
+        };
+
+        Ok(Self { count, from: stack(from)?, to: stack(to)? })
     }
+}
 
-    top
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.from + 1, self.to + 1)
+    }
 }
 
-pub fn part2(input: &str) -> String {
-    let (stacks_s, movs_s) = input
-        .split_once("\n\n")
-        .unwrap();
+// stacks of crates, bottom first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stacks {
+    stacks: Vec<Vec<u8>>,
+}
+
+impl Stacks {
+    // the drawing of the stacks, ending with the row of stack numbers
+    pub fn parse(drawing: &str) -> Result<Self, Error> {
+        let mut rows = drawing.lines().rev();
+
+        // create stacks
+        let numbers = rows.next().unwrap_or(end_of(drawing));
+        let mut stack_count = 0;
+
+        for number in numbers.split_whitespace() {
+            if number.parse() != Ok(stack_count + 1) {
+                return Err(Error::parse(DAY, drawing, number, "the next stack number"));
+            }
 
-    // create stacks
-    let stack_count = stacks_s.find('\n').unwrap() / 4 + 1;
-    let mut stacks: Vec<Vec<u8>> = vec![Vec::new(); stack_count];
+            stack_count += 1;
+        }
 
-    // populate stacks
-    stacks_s
-        .split('\n')
-        .rev()
-        .skip(1)
-        .for_each(|row_s| {
-            let row = row_s.as_bytes();
+        if stack_count == 0 {
+            return Err(Error::parse(DAY, drawing, numbers, "a row of stack numbers"));
+        }
 
-            for i in 0..stack_count {
-                let c = row[i * 4 + 1];
+        let mut stacks: Vec<Vec<u8>> = vec![Vec::new(); stack_count];
 
-                if c != b' ' {
-                    stacks[i].push(c);
+        // populate stacks
+        for row in rows {
+            for (i, stack) in stacks.iter_mut().enumerate() {
+                match row.as_bytes().get(i * 4..).unwrap_or_default() {
+                    [] | [b' ', ..] => (),
+                    [b'[', c, b']', ..] => stack.push(*c),
+                    _ => {
+                        let at = row.get(i * 4..).unwrap_or(row);
+                        return Err(Error::parse(DAY, drawing, at, "a crate like `[A]`"));
+                    },
                 }
             }
-        });
+        }
+
+        Ok(Self { stacks })
+    }
+
+    pub fn len(&self) -> usize {
+        self.stacks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stacks.is_empty()
+    }
+
+    pub fn stack(&self, i: usize) -> &[u8] {
+        &self.stacks[i]
+    }
+
+    // takes the top `count` crates off a stack, returning them bottom first
+    pub fn lift(&mut self, from: usize, count: usize) -> Result<Vec<u8>, Error> {
+        let stack = self.stack_mut(from)?;
+
+        let leave = stack.len().checked_sub(count).ok_or_else(|| {
+            let reason = format!("stack {} has fewer than {} crates", from + 1, count);
+            Error::no_solution(DAY, &reason)
+        })?;
+
+        Ok(stack.split_off(leave))
+    }
+
+    // puts crates on a stack, bottom first
+    pub fn place<I>(&mut self, to: usize, crates: I) -> Result<(), Error>
+    where I: IntoIterator<Item = u8> {
+        self.stack_mut(to)?.extend(crates);
+
+        Ok(())
+    }
+
+    fn stack_mut(&mut self, i: usize) -> Result<&mut Vec<u8>, Error> {
+        self.stacks
+            .get_mut(i)
+            .ok_or_else(|| Error::no_solution(DAY, &format!("there is no stack {}", i + 1)))
+    }
+
+    pub fn apply<C>(&mut self, crane: &C, step: &Move) -> Result<(), Error>
+    where C: CraneModel + ?Sized {
+        crane.apply(self, step)
+    }
+
+    // the crate on top of each stack, skipping empty ones
+    pub fn tops(&self) -> String {
+        self.stacks
+            .iter()
+            .filter_map(|stack| stack.last())
+            .map(|&c| c as char)
+            .collect()
+    }
+}
+
+// the drawing the stacks were parsed from
+impl fmt::Display for Stacks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let height = self.stacks.iter().map(|stack| stack.len()).max().unwrap_or(0);
+
+        for y in (0..height).rev() {
+            let row: Vec<String> = self.stacks
+                .iter()
+                .map(|stack| match stack.get(y) {
+                    Some(&c) => format!("[{}]", c as char),
+                    None => "   ".to_string(),
+                })
+                .collect();
+
+            writeln!(f, "{}", row.join(" "))?;
+        }
+
+        let numbers: Vec<String> = (1..=self.len())
+            .map(|i| format!(" {} ", i))
+            .collect();
 
-    // execute moves
-    movs_s
-        .trim()
-        .split([' ', '\n'])
-        .collect::<Vec<_>>()
-        .chunks(6)
-        .for_each(|instr| {
-            let (count, src, dst) = (
-                instr.get(1).unwrap().parse::<usize>().unwrap(),
-                instr.get(3).unwrap().parse::<usize>().unwrap() - 1,
-                instr.get(5).unwrap().parse::<usize>().unwrap() - 1,
-            );
+        writeln!(f, "{}", numbers.join(" "))
+    }
+}
+
+// how a crane carries out a move
+pub trait CraneModel {
+    fn apply(&self, stacks: &mut Stacks, step: &Move) -> Result<(), Error>;
+}
+
+// moves crates one at a time
+pub struct CrateMover9000;
+
+impl CraneModel for CrateMover9000 {
+    fn apply(&self, stacks: &mut Stacks, step: &Move) -> Result<(), Error> {
+        let crates = stacks.lift(step.from, step.count)?;
+        stacks.place(step.to, crates.into_iter().rev())
+    }
+}
+
+// moves several crates at once, keeping their order
+pub struct CrateMover9001;
+
+impl CraneModel for CrateMover9001 {
+    fn apply(&self, stacks: &mut Stacks, step: &Move) -> Result<(), Error> {
+        let crates = stacks.lift(step.from, step.count)?;
+        stacks.place(step.to, crates)
+    }
+}
+
+pub fn parse(input: &str) -> Result<(Stacks, Vec<Move>), Error> {
+    let (drawing, moves) = input
+        .split_once("\n\n")
+        .ok_or_else(|| Error::parse(DAY, input, end_of(input), "a blank line and moves"))?;
 
+    let stacks = Stacks::parse(drawing)?;
 
-            let leave = stacks[src].len() - count;
-            let mut containers = stacks[src].split_off(leave);
-            stacks[dst].append(&mut containers);
+    let moves = moves
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| Move::parse(input, line))
+        .collect::<Result<Vec<_>, _>>()?;
 
-        });
+    Ok((stacks, moves))
+}
+
+// the crates on top once `crane` has made every move
+pub fn rearrange<C: CraneModel + ?Sized>(input: &str, crane: &C) -> Result<String, Error> {
+    let (mut stacks, moves) = parse(input)?;
 
-    let mut top = String::new(); 
-    for stack in stacks {
-        top.push(*stack.last().unwrap() as char);
+    for step in &moves {
+        stacks.apply(crane, step)?;
     }
 
-    top
+    Ok(stacks.tops())
+}
+
+pub fn try_part1(input: &str) -> Result<String, Error> {
+    rearrange(input, &CrateMover9000)
+}
+
+pub fn try_part2(input: &str) -> Result<String, Error> {
+    rearrange(input, &CrateMover9001)
+}
+
+pub fn part1(input: &str) -> String {
+    try_part1(input).unwrap()
+}
+
+pub fn part2(input: &str) -> String {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day05::{part1, part2};
+    use crate::day05::{
+        parse, part1, part2, rearrange, try_part1, CraneModel, CrateMover9000, Move, Stacks,
+    };
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "    [D]    \n\
                               [N] [C]    \n\
@@ -122,10 +252,75 @@ mod tests {
     fn test_part1() {
         assert_eq!(part1(TEST_INPUT), "CMZ");
     }
-    
+
     #[test]
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), "MCD");
     }
-}
 
+    #[test]
+    fn test_stacks() {
+        let (mut stacks, moves) = parse(TEST_INPUT).unwrap();
+        let drawing = TEST_INPUT.split_once("\n\n").unwrap().0;
+
+        assert_eq!(stacks.to_string(), format!("{}\n", drawing));
+        assert_eq!(moves[1], Move { count: 3, from: 0, to: 2 });
+        assert_eq!(moves[1].to_string(), "move 3 from 1 to 3");
+
+        stacks.apply(&CrateMover9000, &moves[0]).unwrap();
+        stacks.apply(&CrateMover9000, &moves[1]).unwrap();
+
+        assert_eq!(
+            stacks.to_string(),
+            "        [Z]\n\
+             \x20       [N]\n\
+             \x20   [C] [D]\n\
+             \x20   [M] [P]\n\
+             \x201   2   3 \n"
+        );
+        assert_eq!(stacks.tops(), "CZ");
+        assert_eq!(Stacks::parse(&stacks.to_string()), Ok(stacks));
+    }
+
+    #[test]
+    fn test_crane_model() {
+        // turns the lifted crates so that the top one ends up at the bottom
+        struct Juggler;
+
+        impl CraneModel for Juggler {
+            fn apply(&self, stacks: &mut Stacks, step: &Move) -> Result<(), Error> {
+                let mut crates = stacks.lift(step.from, step.count)?;
+                crates.rotate_right(1);
+                stacks.place(step.to, crates)
+            }
+        }
+
+        assert_eq!(rearrange(TEST_INPUT, &Juggler), Ok("CMN".to_string()));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            try_part1("garbage\n"),
+            Err(Error::Parse {
+                day: 5,
+                line: 2,
+                column: 1,
+                expected: "a blank line and moves".to_string()
+            })
+        );
+        assert_eq!(
+            try_part1("[A]\n 1 \n\nmove 1 from 1 to 0\n"),
+            Err(Error::Parse {
+                day: 5,
+                line: 4,
+                column: 18,
+                expected: "a stack number".to_string()
+            })
+        );
+        assert_eq!(
+            try_part1("[A]\n 1 \n\nmove 2 from 1 to 1\n"),
+            Err(Error::no_solution(5, "stack 1 has fewer than 2 crates"))
+        );
+    }
+}
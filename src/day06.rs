@@ -1,4 +1,8 @@
-fn find_marker(input: &str, packet_len: usize) -> Result<usize, ()> {
+use crate::error::Error;
+
+const DAY: u8 = 6;
+
+fn find_marker(input: &str, packet_len: usize) -> Result<usize, Error> {
     let bytes = input.as_bytes();
 
     // create hash table
@@ -30,20 +34,28 @@ fn find_marker(input: &str, packet_len: usize) -> Result<usize, ()> {
         table[p as usize] -= 1;
     }
 
-    Err(())
+    Err(Error::no_solution(DAY, "no marker found"))
+}
+
+pub fn try_part1(input: &str) -> Result<usize, Error> {
+    find_marker(input, 4)
+}
+
+pub fn try_part2(input: &str) -> Result<usize, Error> {
+    find_marker(input, 14)
 }
 
 pub fn part1(input: &str) -> usize {
-    find_marker(input, 4).unwrap()
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> usize {
-    find_marker(input, 14).unwrap()
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day06::{part1, part2};
+    use crate::day06::{part1, part2, try_part1};
 
     const TEST_INPUTS: [&str; 5] = [
         "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
@@ -70,5 +82,10 @@ mod tests {
         assert_eq!(part2(TEST_INPUTS[3]), 29);
         assert_eq!(part2(TEST_INPUTS[4]), 26);
     }
+
+    #[test]
+    fn test_no_marker() {
+        assert!(try_part1("aaaaaaaa").is_err());
+    }
 }
 
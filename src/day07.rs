@@ -1,13 +1,49 @@
 // Idea for tree arena:
 // https://dev.to/deciduously/no-more-tears-no-more-knots-arena-allocated-trees-in-rust-44k6
 
+use std::cell::OnceCell;
+use std::fmt;
+
+use crate::error::Error;
+use crate::params::{self, value};
+
+const DAY: u8 = 7;
+
+// the size of the disk, and how much of it must be free for the update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Params {
+    pub disk: usize,
+    pub needed: usize,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self { disk: 70000000, needed: 30000000 }
+    }
+}
+
+impl params::Params for Params {
+    fn example() -> Self {
+        Self::default()
+    }
+
+    fn set(&mut self, name: &str, val: &str) -> Result<(), String> {
+        match name {
+            "disk" => self.disk = value(val, "a size")?,
+            "needed" => self.needed = value(val, "a size")?,
+            _ => return Err("`disk` or `needed`".to_string()),
+        }
+
+        Ok(())
+    }
+}
+
 enum NodeType {
     Directory { children: Vec<usize> },
     File { size: usize }
 }
 
 struct TreeNode {
-    index: usize,
     name: String,
     parent: Option<usize>,
     value: NodeType
@@ -15,139 +51,265 @@ struct TreeNode {
 
 impl TreeNode {
     fn new_file(name: String, size: usize, parent: Option<usize>) -> Self {
-        Self { index: 0, name, parent, value: NodeType::File { size } }
+        Self { name, parent, value: NodeType::File { size } }
     }
 
     fn new_directory(name: String, parent: Option<usize>) -> Self {
-        Self {
-            index: 0,
-            name,
-            parent,
-            value: NodeType::Directory { children: vec![] }
-        }
+        Self { name, parent, value: NodeType::Directory { children: vec![] } }
     }
 }
 
-struct TreeArena {
-    arena: Vec<TreeNode>
+// the tree of files and directories that the terminal output explores, with
+// nodes referred to by index. the root directory is node 0.
+pub struct Filesystem {
+    arena: Vec<TreeNode>,
+    sizes: Vec<OnceCell<usize>>,
 }
 
-impl TreeArena {
-    fn push(&mut self, mut node: TreeNode) -> usize {
-        let index = self.arena.len();
-        node.index = index;
+impl Filesystem {
+    pub const ROOT: usize = 0;
 
-        self.arena.push(node);
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        let mut fs = Self { arena: vec![], sizes: vec![] };
 
-        if let Some(parent) = self.arena[index].parent {
-            if let NodeType::Directory { 
-                children
-            } = &mut self.arena[parent].value {
+        // add root directory and set working directory
+        let mut cwd = fs.push(TreeNode::new_directory("/".to_string(), None));
+
+        for line in input.lines().filter(|line| !line.is_empty()) {
+            let args: Vec<&str> = line.split(' ').collect();
+
+            match args[..] {
+                ["$", "cd", "/"] => cwd = Self::ROOT,
+                ["$", "cd", ".."] => cwd = fs.parent(cwd).unwrap_or(Self::ROOT),
+                ["$", "cd", name] => {
+                    // directories may be entered without being listed first
+                    cwd = match fs.child(cwd, name) {
+                        Some(child) if fs.is_dir(child) => child,
+                        Some(_) => {
+                            return Err(Error::parse(DAY, input, name, "a directory"));
+                        },
+                        None => {
+                            let directory = TreeNode::new_directory(name.to_string(), Some(cwd));
+                            fs.push(directory)
+                        },
+                    };
+                },
+                ["$", "ls"] => (),
+                ["$", command, ..] => {
+                    return Err(Error::parse(DAY, input, command, "`cd` or `ls`"));
+                },
+                ["dir", name] => {
+                    if fs.child(cwd, name).is_none() {
+                        fs.push(TreeNode::new_directory(name.to_string(), Some(cwd)));
+                    }
+                },
+                [size, name] => {
+                    let size = size
+                        .parse()
+                        .map_err(|_| Error::parse(DAY, input, size, "a size or `dir`"))?;
+
+                    if fs.child(cwd, name).is_none() {
+                        fs.push(TreeNode::new_file(name.to_string(), size, Some(cwd)));
+                    }
+                },
+                _ => return Err(Error::parse(DAY, input, line, "a command or listing")),
+            }
+        }
+
+        Ok(fs)
+    }
+
+    fn push(&mut self, node: TreeNode) -> usize {
+        let index = self.arena.len();
+
+        if let Some(parent) = node.parent {
+            if let NodeType::Directory { children } = &mut self.arena[parent].value {
                 children.push(index);
             }
         }
 
+        self.arena.push(node);
+        self.sizes.push(OnceCell::new());
+
         index
     }
 
-    // calculate node size recursively
-    fn size(&self, index: usize) -> usize {
+    pub fn len(&self) -> usize {
+        self.arena.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.arena.is_empty()
+    }
+
+    pub fn name(&self, index: usize) -> &str {
+        &self.arena[index].name
+    }
+
+    pub fn parent(&self, index: usize) -> Option<usize> {
+        self.arena[index].parent
+    }
+
+    pub fn is_dir(&self, index: usize) -> bool {
+        matches!(self.arena[index].value, NodeType::Directory { .. })
+    }
+
+    // the entries of a directory, in the order they were found; none for files
+    pub fn children(&self, index: usize) -> &[usize] {
         match &self.arena[index].value {
-            NodeType::Directory { children }  => {
+            NodeType::Directory { children } => children,
+            NodeType::File { .. } => &[],
+        }
+    }
+
+    pub fn child(&self, index: usize, name: &str) -> Option<usize> {
+        self.children(index)
+            .iter()
+            .copied()
+            .find(|&child| self.name(child) == name)
+    }
+
+    // the node at an absolute path such as `/a/e`
+    pub fn resolve(&self, path: &str) -> Option<usize> {
+        path
+            .strip_prefix('/')?
+            .split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(Self::ROOT, |index, name| self.child(index, name))
+    }
+
+    pub fn path(&self, index: usize) -> String {
+        let mut names = vec![];
+        let mut node = index;
+
+        while let Some(parent) = self.parent(node) {
+            names.push(self.name(node));
+            node = parent;
+        }
+
+        names.reverse();
+
+        format!("/{}", names.join("/"))
+    }
+
+    // every node below and including `index`, parents before their children
+    pub fn walk(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut stack = vec![index];
+
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(self.children(node).iter().rev());
+
+            Some(node)
+        })
+    }
+
+    pub fn directories(&self) -> impl Iterator<Item = usize> + '_ {
+        self.walk(Self::ROOT).filter(|&index| self.is_dir(index))
+    }
+
+    // calculate node size recursively, once per node
+    pub fn size(&self, index: usize) -> usize {
+        *self.sizes[index].get_or_init(|| match &self.arena[index].value {
+            NodeType::Directory { children } => {
                 children
                     .iter()
-                    .map(|child_index| self.size(*child_index))
+                    .map(|&child| self.size(child))
                     .sum()
             },
-            NodeType::File { size } => { *size }
-        }      
-    }
-}
-
-fn parse_filetree(input: &str) -> TreeArena {
-    // initialize tree
-    let mut tree = TreeArena { arena: Vec::new() };
-
-    // add root directory and set working directory
-    let root_dir = TreeNode::new_directory("/".to_string(), None);
-    let mut cwd = tree.push(root_dir);
-
-    // parse input line by line
-    input
-        .trim()
-        .split('\n')
-        .for_each(|line| {
-            let args = line.split(' ').collect::<Vec<_>>();
-
-            if args[0] == "$"  && args[1] == "cd" { 
-                // navigate directories
-                match args[2] {
-                    "/" => cwd = 0,
-                    ".." => if let Some(index) = tree.arena[cwd].parent {
-                        cwd = index;
-                    },
-                    _ => {
-                        // add new directory to tree
-                        let name = args[2].to_string();
-                        let new_dir = TreeNode::new_directory(name, Some(cwd));
-
-                        cwd = tree.push(new_dir);
-                    }
-                }
+            NodeType::File { size } => *size,
+        })
+    }
+
+    fn render(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        index: usize,
+        depth: usize
+    ) -> fmt::Result {
+        let (indent, name) = ("  ".repeat(depth), self.name(index));
 
-            } else if args[0].as_bytes()[0].is_ascii_digit() {
-                // add new file to tree
-                let (name, size) = (
-                    args[1].to_string(),
-                    args[0].parse::<usize>().unwrap()
-                );
+        match self.arena[index].value {
+            NodeType::Directory { .. } => writeln!(f, "{}- {} (dir)", indent, name)?,
+            NodeType::File { size } => {
+                writeln!(f, "{}- {} (file, size={})", indent, name, size)?
+            },
+        }
 
-                let file = TreeNode::new_file(name, size, Some(cwd));
+        let mut children = self.children(index).to_vec();
+        children.sort_by_key(|&child| self.name(child));
 
-                tree.push(file);
-            }
-        });
+        for child in children {
+            self.render(f, child, depth + 1)?;
+        }
 
-    tree
+        Ok(())
+    }
 }
 
-pub fn part1(input: &str) -> usize {
-    let tree = parse_filetree(input);
+// a listing like the puzzle's, with entries sorted by name
+impl fmt::Display for Filesystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render(f, Self::ROOT, 0)
+    }
+}
 
-    tree
-        .arena
-        .iter()
-        .filter(|entry| matches!(entry.value, NodeType::Directory {children: _}))
-        .map(|directory| tree.size(directory.index))
+// the directory to delete to make room for the update
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deletion {
+    pub path: String,
+    pub size: usize,
+}
+
+pub fn try_part1_with(input: &str, _params: &Params) -> Result<usize, Error> {
+    let fs = Filesystem::parse(input)?;
+
+    Ok(fs
+        .directories()
+        .map(|directory| fs.size(directory))
         .filter(|size| *size <= 100000)
-        .sum()
+        .sum())
 }
 
-pub fn part2(input: &str) -> usize {
-    const TOTAL: usize = 70000000;
-    const NEEDED: usize = 30000000;
+pub fn try_part2_with(input: &str, params: &Params) -> Result<Deletion, Error> {
+    let fs = Filesystem::parse(input)?;
+
+    let free = params
+        .disk
+        .checked_sub(fs.size(Filesystem::ROOT))
+        .ok_or_else(|| Error::no_solution(DAY, "the files don't fit on the disk"))?;
+    let missing = params.needed.saturating_sub(free);
 
-    let tree = parse_filetree(input);
-    let used = tree.size(0);
+    fs.directories()
+        .filter(|&directory| fs.size(directory) >= missing)
+        .min_by_key(|&directory| fs.size(directory))
+        .map(|directory| Deletion {
+            path: fs.path(directory),
+            size: fs.size(directory),
+        })
+        .ok_or_else(|| Error::no_solution(DAY, "no directory is large enough"))
+}
+
+pub fn try_part1(input: &str) -> Result<usize, Error> {
+    try_part1_with(input, &Params::default())
+}
 
-    let mut dir_sizes = tree
-        .arena
-        .iter()
-        .filter(|entry| matches!(entry.value, NodeType::Directory {children: _}))
-        .map(|directory| tree.size(directory.index))
-        .collect::<Vec<_>>();
+pub fn try_part2(input: &str) -> Result<Deletion, Error> {
+    try_part2_with(input, &Params::default())
+}
 
-    dir_sizes.sort();
+pub fn part1(input: &str) -> usize {
+    try_part1(input).unwrap()
+}
 
-    *dir_sizes
-        .iter()
-        .find(|size| TOTAL - used + *size >= NEEDED)
-        .unwrap()
+pub fn part2(input: &str) -> Deletion {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day07::{part1, part2};
+    use crate::day07::{part1, part2, try_part1, try_part2_with, Deletion, Filesystem, Params};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "$ cd /\n\
                               $ ls\n\
@@ -180,7 +342,71 @@ mod tests {
     
     #[test]
     fn test_part2() {
-        assert_eq!(part2(TEST_INPUT), 24933642)
+        assert_eq!(
+            part2(TEST_INPUT),
+            Deletion { path: "/d".to_string(), size: 24933642 }
+        );
     }
-}
 
+    #[test]
+    fn test_filesystem() {
+        let fs = Filesystem::parse(TEST_INPUT).unwrap();
+        let e = fs.resolve("/a/e").unwrap();
+
+        assert_eq!(fs.resolve("/"), Some(Filesystem::ROOT));
+        assert_eq!(fs.resolve("/a/x"), None);
+        assert_eq!(fs.path(e), "/a/e");
+        assert_eq!(fs.size(e), 584);
+        assert_eq!(fs.size(fs.resolve("/a").unwrap()), 94853);
+        assert_eq!(fs.len(), 14);
+
+        let names = |nodes: Vec<usize>| -> Vec<&str> {
+            nodes.into_iter().map(|node| fs.name(node)).collect()
+        };
+
+        assert_eq!(names(fs.children(Filesystem::ROOT).to_vec()), ["a", "b.txt", "c.dat", "d"]);
+        assert_eq!(names(fs.directories().collect()), ["/", "a", "e", "d"]);
+        assert!(fs.children(fs.resolve("/b.txt").unwrap()).is_empty());
+
+        assert_eq!(
+            fs.to_string(),
+            "- / (dir)\n\
+             \x20 - a (dir)\n\
+             \x20   - e (dir)\n\
+             \x20     - i (file, size=584)\n\
+             \x20   - f (file, size=29116)\n\
+             \x20   - g (file, size=2557)\n\
+             \x20   - h.lst (file, size=62596)\n\
+             \x20 - b.txt (file, size=14848514)\n\
+             \x20 - c.dat (file, size=8504156)\n\
+             \x20 - d (dir)\n\
+             \x20   - d.ext (file, size=5626152)\n\
+             \x20   - d.log (file, size=8033020)\n\
+             \x20   - j (file, size=4060174)\n\
+             \x20   - k (file, size=7214296)\n"
+        );
+    }
+
+    #[test]
+    fn test_params() {
+        let params = Params { disk: 50000000, needed: 40000000 };
+        let deletion = try_part2_with(TEST_INPUT, &params).unwrap();
+
+        assert_eq!((deletion.path.as_str(), deletion.size), ("/", 48381165));
+
+        let params = Params { disk: 100000000, needed: 30000000 };
+        let deletion = try_part2_with(TEST_INPUT, &params).unwrap();
+
+        assert_eq!((deletion.path.as_str(), deletion.size), ("/a/e", 584));
+
+        assert_eq!(
+            try_part1("$ cd /\n$ rm -rf a\n"),
+            Err(Error::Parse {
+                day: 7,
+                line: 2,
+                column: 3,
+                expected: "`cd` or `ls`".to_string()
+            })
+        );
+    }
+}
@@ -1,100 +1,87 @@
-struct Grid<'a> {
-    rows: Vec<&'a [u8]>,
-    cols: Vec<Vec<u8>>,
-}
+use crate::error::{end_of, Error};
+use crate::grid::{Grid, NEIGHBOURS4};
 
-impl<'a> Grid<'a> {
+const DAY: u8 = 8;
 
-    fn new(input: &'a str) -> Grid<'a> {
-        let rows = input
-            .trim()
-            .as_bytes()
-            .split(|&b| b == b'\n')
-            .collect::<Vec<_>>();
+// a rectangle of tree heights
+fn parse(input: &str) -> Result<Grid<u8>, Error> {
+    let rows: Vec<&str> = input.trim().lines().collect();
+    let width = rows.first().map_or(0, |row| row.len());
 
-        let cols = (0..rows.len())
-            .map(|col| rows.iter().map(move |row| row[col]).collect::<Vec<_>>())
-            .collect::<Vec<_>>();
+    for row in &rows {
+        if let Some(i) = row.find(|c: char| !c.is_ascii_digit()) {
+            return Err(Error::parse(DAY, input, &row[i..], "a tree height"));
+        }
 
-        Self { rows, cols }
+        if row.len() != width {
+            let at = row.get(width..).unwrap_or(end_of(row));
+            return Err(Error::parse(DAY, input, at, "rows of equal length"));
+        }
     }
 
-    fn is_visible(&self, x: usize, y: usize) -> bool {
-        let tree = self.rows[y][x];
+    Ok(Grid::parse(input.trim()))
+}
 
-        x == 0 || x == self.rows.len() - 1
-        || y == 0 || y == self.cols.len() - 1
-        || self.rows[y][..x].iter().all(|&neighbour| tree > neighbour) 
-        || self.rows[y][(x + 1)..].iter().all(|&neighbour| tree > neighbour)
-        || self.cols[x][..y].iter().all(|&neighbour| tree > neighbour)
-        || self.cols[x][(y + 1)..].iter().all(|&neighbour| tree > neighbour)
-    }
+fn is_visible(grid: &Grid<u8>, pos: (usize, usize)) -> bool {
+    let tree = grid[pos];
 
-    fn score(&self, x: usize, y: usize) -> usize {
-        let tree = &self.rows[y][x];
+    NEIGHBOURS4
+        .iter()
+        .any(|&d| grid.ray(pos, d).all(|neighbour| tree > grid[neighbour]))
+}
 
-        let mut left = self.rows[y][..x].iter().rev();
-        let mut right = self.rows[y][(x + 1)..].iter();
-        let mut above = self.cols[x][..y].iter().rev();
-        let mut below = self.cols[x][(y + 1)..].iter();
+fn score(grid: &Grid<u8>, pos: (usize, usize)) -> usize {
+    let tree = grid[pos];
 
-        let lscore = left
-            .position(|neighbour| neighbour >= tree)
-            .map_or(x, |position| position + 1);
+    NEIGHBOURS4
+        .iter()
+        .map(|&d| {
+            let mut trees = 0;
 
-        let rscore = right
-            .position(|neighbour| neighbour >= tree)
-            .map_or(self.rows[y].len() - 1 - x, |position| position + 1);
-        
-        let ascore = above
-            .position(|neighbour| neighbour >= tree)
-            .map_or(y, |position| position + 1);
+            for neighbour in grid.ray(pos, d) {
+                trees += 1;
 
-        let bscore = below
-            .position(|neighbour| neighbour >= tree)
-            .map_or(self.cols[x].len() - 1 - y, |position| position + 1);
+                if grid[neighbour] >= tree {
+                    break;
+                }
+            }
 
-        lscore * rscore * ascore * bscore
-    }
+            trees
+        })
+        .product()
 }
 
-pub fn part1(input: &str) -> usize {
-    let grid = Grid::new(input);
-
-    let mut visible = 0;
-
-    for y in 0..grid.rows.len() {
-        for x in 0..grid.rows.len() {
-            if grid.is_visible(x, y) {
-                visible += 1;
-            }
-        }
-    }
+pub fn try_part1(input: &str) -> Result<usize, Error> {
+    let grid = parse(input)?;
 
-    visible
+    Ok(grid
+        .positions()
+        .filter(|&pos| is_visible(&grid, pos))
+        .count())
 }
 
-pub fn part2(input: &str) -> usize {
-    let grid = Grid::new(input);
-
-    let mut max = 0;
+pub fn try_part2(input: &str) -> Result<usize, Error> {
+    let grid = parse(input)?;
 
-    for y in 0..grid.rows.len() {
-        for x in 0..grid.rows[y].len() {
-            let score = grid.score(x, y);
+    Ok(grid
+        .positions()
+        .map(|pos| score(&grid, pos))
+        .max()
+        .unwrap_or(0))
+}
 
-            if score > max {
-                max = score;
-            }
-        }
-    }
+pub fn part1(input: &str) -> usize {
+    try_part1(input).unwrap()
+}
 
-    max
+pub fn part2(input: &str) -> usize {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day08::{part1, part2};
+    use crate::day08::{part1, part2, try_part1};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "30373\n\
                               25512\n\
@@ -111,5 +98,19 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 8)
     }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            try_part1("303\n25\n"),
+            Err(Error::Parse {
+                day: 8,
+                line: 2,
+                column: 3,
+                expected: "rows of equal length".to_string()
+            })
+        );
+        assert!(try_part1("30x\n255\n").is_err());
+    }
 }
 
@@ -1,3 +1,7 @@
+use crate::error::Error;
+
+const DAY: u8 = 9;
+
 #[derive(Clone)]
 #[derive(Copy)]
 struct Knot {
@@ -53,57 +57,83 @@ impl Rope {
     }
 }
 
-fn visited(input: &str, rope_len: usize) -> usize {
+// each line's direction and distance
+fn motions(input: &str) -> Result<Vec<(u8, usize)>, Error> {
+    input
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (dir, dist) = line
+                .split_once(' ')
+                .ok_or_else(|| Error::parse(DAY, input, line, "a motion like `R 4`"))?;
+
+            let dir = match dir {
+                "U" | "D" | "L" | "R" => dir.as_bytes()[0],
+                _ => return Err(Error::parse(DAY, input, dir, "`U`, `D`, `L` or `R`")),
+            };
+
+            let dist = dist
+                .parse::<usize>()
+                .map_err(|_| Error::parse(DAY, input, dist, "a number of steps"))?;
+
+            Ok((dir, dist))
+        })
+        .collect()
+}
+
+fn visited(input: &str, rope_len: usize) -> Result<usize, Error> {
     let mut visited = [[false; 1000]; 1000];
     
     let mut rope = Rope {
         knots: vec![Knot { x: 0, y: 0 }; rope_len]
     };
 
-    input
-        .trim()
-        .split([' ', '\n'])
-        .collect::<Vec<_>>()
-        .chunks(2)
-        .for_each(|instr| {
-            let (dir, dist) = (
-                instr[0].as_bytes()[0],
-                instr[1].parse::<usize>().unwrap()
-            );
-
-            for _ in 0..dist {
-                rope.step(dir);
+    for (dir, dist) in motions(input)? {
+        for _ in 0..dist {
+            rope.step(dir);
 
-                let (x, y) = (
-                    (rope.knots[rope_len - 1].y + 500) as usize,
-                    (rope.knots[rope_len - 1].x + 500) as usize
-                );
+            let (x, y) = (
+                rope.knots[rope_len - 1].y + 500,
+                rope.knots[rope_len - 1].x + 500
+            );
 
-                visited[y][x] = true;
+            if !(0..1000).contains(&x) || !(0..1000).contains(&y) {
+                return Err(Error::no_solution(DAY, "the tail leaves the 1000 by 1000 area"));
             }
 
-        });
+            visited[y as usize][x as usize] = true;
+        }
+    }
 
-    visited
+    Ok(visited
         .iter()
         .map(|row| row
              .iter()
              .filter(|&pos| *pos)
              .count())
-        .sum()
+        .sum())
 }
 
-pub fn part1(input: &str) -> usize {
+pub fn try_part1(input: &str) -> Result<usize, Error> {
     visited(input, 2)
 }
 
-pub fn part2(input: &str) -> usize {
+pub fn try_part2(input: &str) -> Result<usize, Error> {
     visited(input, 10)
 }
 
+pub fn part1(input: &str) -> usize {
+    try_part1(input).unwrap()
+}
+
+pub fn part2(input: &str) -> usize {
+    try_part2(input).unwrap()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, try_part1, try_part2};
+    use crate::error::Error;
 
     const TEST_INPUT_1: &str = "R 4\n\
                                 U 4\n\
@@ -132,5 +162,20 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT_2), 36);
     }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            try_part1("R 4\nX 4\n"),
+            Err(Error::Parse {
+                day: 9,
+                line: 2,
+                column: 1,
+                expected: "`U`, `D`, `L` or `R`".to_string()
+            })
+        );
+        assert!(try_part1("R 4\nU\n").is_err());
+        assert!(try_part2("R 600\n").is_err());
+    }
 }
 
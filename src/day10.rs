@@ -1,68 +1,205 @@
-pub fn part1(input: &str) -> i64 {
-    let mut cycle = 0;
-    let mut acc = 1;
+use std::str::FromStr;
 
-    let mut signal_strength = 0;
+use crate::error::Error;
+use crate::params::{self, value};
 
-    input
-        .trim()
-        .split([' ', '\n'])
-        .for_each(|arg| {
-            cycle += 1;
+const DAY: u8 = 10;
 
-            if cycle == 20 || (cycle - 20) % 40 == 0 {
-                signal_strength += cycle * acc;
-            }
+// the cycles to sample the signal strength during, and the size of the CRT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Params {
+    pub first: usize,
+    pub interval: usize,
+    pub width: usize,
+    pub height: usize,
+}
 
-            let op = arg.chars().next().unwrap();
+impl Default for Params {
+    fn default() -> Self {
+        Self { first: 20, interval: 40, width: 40, height: 6 }
+    }
+}
 
-            if op != 'a' && op != 'n' {
-                let addend = arg.parse::<i64>().unwrap();
-                acc += addend;
-            }
-        });
+impl params::Params for Params {
+    fn example() -> Self {
+        Self::default()
+    }
+
+    fn set(&mut self, name: &str, val: &str) -> Result<(), String> {
+        let positive = |val: &str| match value(val, "a positive number")? {
+            0 => Err("a positive number".to_string()),
+            n => Ok(n),
+        };
 
-    signal_strength
+        match name {
+            "first" => self.first = positive(val)?,
+            "interval" => self.interval = positive(val)?,
+            "width" => self.width = positive(val)?,
+            "height" => self.height = value(val, "a number")?,
+            _ => return Err("one of `first`, `interval`, `width` or `height`".to_string()),
+        }
+
+        Ok(())
+    }
 }
 
-pub fn part2(input: &str) -> String {
-    let mut cycle = 0;
-    let mut acc = 1;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Noop,
+    Addx(i64),
+}
+
+impl Instruction {
+    // how many cycles the instruction takes to complete
+    pub fn cycles(&self) -> usize {
+        match self {
+            Instruction::Noop => 1,
+            Instruction::Addx(_) => 2,
+        }
+    }
 
-    let mut pixbuf = String::new();
+    fn execute(&self, x: &mut i64) {
+        match self {
+            Instruction::Noop => (),
+            Instruction::Addx(addend) => *x += addend,
+        }
+    }
+}
 
+impl FromStr for Instruction {
+    // what was expected
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(' ') {
+            None if s == "noop" => Ok(Instruction::Noop),
+            Some(("addx", addend)) => addend
+                .parse()
+                .map(Instruction::Addx)
+                .map_err(|_| "a number"),
+            _ => Err("`noop` or `addx`"),
+        }
+    }
+}
+
+fn parse(input: &str) -> Result<Vec<Instruction>, Error> {
     input
-        .trim()
-        .split([' ', '\n'])
-        .for_each(|arg| {
-            cycle += 1;
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.parse().map_err(|expected| Error::parse(DAY, input, line, expected)))
+        .collect()
+}
+
+// a cycle, counted from 1, and the X register while it runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub number: usize,
+    pub x: i64,
+}
+
+impl Cycle {
+    pub fn signal_strength(&self) -> i64 {
+        self.number as i64 * self.x
+    }
+}
+
+// runs a program, yielding each cycle until the last instruction completes.
+// instructions take effect at the end of their last cycle.
+pub struct Cpu {
+    program: Vec<Instruction>,
+    pc: usize,
+    x: i64,
+    cycle: usize,
+    // cycles the current instruction has been running for
+    busy: usize,
+}
+
+impl Cpu {
+    pub fn new(program: Vec<Instruction>) -> Self {
+        Self { program, pc: 0, x: 1, cycle: 0, busy: 0 }
+    }
+
+    pub fn x(&self) -> i64 {
+        self.x
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.pc >= self.program.len()
+    }
+}
+
+impl Iterator for Cpu {
+    type Item = Cycle;
+
+    fn next(&mut self) -> Option<Cycle> {
+        let instruction = *self.program.get(self.pc)?;
 
-            let column: i64 = (cycle - 1) % 40;
+        self.cycle += 1;
+        self.busy += 1;
 
-            if column == 0 && cycle > 1 {
-                pixbuf.push('\n')
-            }
+        let cycle = Cycle { number: self.cycle, x: self.x };
 
-            if (column - acc).abs() < 2 {
-                pixbuf.push('#');
-            } else {
-                pixbuf.push('.');
-            }
+        if self.busy == instruction.cycles() {
+            instruction.execute(&mut self.x);
+            self.pc += 1;
+            self.busy = 0;
+        }
 
-            let op = arg.chars().next().unwrap();
+        Some(cycle)
+    }
+}
 
-            if op != 'a' && op != 'n' {
-                let addend = arg.parse::<i64>().unwrap();
-                acc += addend;
-            }
-        });
+pub fn try_part1_with(input: &str, params: &Params) -> Result<i64, Error> {
+    let cpu = Cpu::new(parse(input)?);
 
-    pixbuf
+    Ok(cpu
+        .skip(params.first - 1)
+        .step_by(params.interval)
+        .map(|cycle| cycle.signal_strength())
+        .sum())
+}
+
+pub fn try_part2_with(input: &str, params: &Params) -> Result<String, Error> {
+    let mut cpu = Cpu::new(parse(input)?);
+    let mut rows = vec![];
+
+    // the sprite is three pixels wide, centred on X
+    for _ in 0..params.height {
+        let row: String = (0..params.width as i64)
+            .map(|column| match cpu.next() {
+                Some(cycle) if (column - cycle.x).abs() < 2 => '#',
+                _ => '.',
+            })
+            .collect();
+
+        rows.push(row);
+    }
+
+    Ok(rows.join("\n"))
+}
+
+pub fn try_part1(input: &str) -> Result<i64, Error> {
+    try_part1_with(input, &Params::default())
+}
+
+pub fn try_part2(input: &str) -> Result<String, Error> {
+    try_part2_with(input, &Params::default())
+}
+
+pub fn part1(input: &str) -> i64 {
+    try_part1(input).unwrap()
+}
+
+pub fn part2(input: &str) -> String {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{
+        part1, part2, try_part1, try_part1_with, try_part2_with, Cpu, Cycle, Instruction, Params,
+    };
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "addx 15\n\
                               addx -11\n\
@@ -227,5 +364,44 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), PART2_TEST_OUTPUT);
     }
-}
 
+    #[test]
+    fn test_cpu() {
+        let program = vec![Instruction::Noop, Instruction::Addx(3), Instruction::Addx(-5)];
+        let mut cpu = Cpu::new(program);
+        let x: Vec<i64> = cpu.by_ref().map(|cycle| cycle.x).collect();
+
+        assert_eq!(x, [1, 1, 1, 4, 4]);
+        assert_eq!(cpu.x(), -1);
+        assert!(cpu.is_halted());
+
+        assert_eq!("addx -7".parse(), Ok(Instruction::Addx(-7)));
+        assert_eq!(Instruction::Noop.cycles(), 1);
+        assert_eq!(Cycle { number: 20, x: 21 }.signal_strength(), 420);
+
+        assert_eq!(
+            try_part1("noop\naddx x\n"),
+            Err(Error::Parse {
+                day: 10,
+                line: 2,
+                column: 1,
+                expected: "a number".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_params() {
+        let params = Params { first: 1, interval: 1, width: 8, height: 2 };
+
+        assert_eq!(try_part2_with(TEST_INPUT, &params), Ok("##..##..\n........".to_string()));
+        assert_eq!(
+            try_part2_with("noop\n", &params),
+            Ok("#.......\n........".to_string())
+        );
+
+        // only the first of the default samples
+        let params = Params { interval: 1000, ..Params::default() };
+        assert_eq!(try_part1_with(TEST_INPUT, &params), Ok(420));
+    }
+}
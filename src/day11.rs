@@ -1,108 +1,283 @@
 use std::collections::VecDeque;
+use std::iter::Peekable;
+use std::str::{FromStr, Lines, SplitWhitespace};
+
+use crate::error::{end_of, Error};
+use crate::expression::{Expr, Operator};
+use crate::params::{self, value};
+
+const DAY: u8 = 11;
+
+// how many rounds the monkeys play, what worry levels are divided by after
+// each inspection, and how many of the most active monkeys count towards
+// monkey business. rounds and relief default to the part's own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Params {
+    pub rounds: Option<usize>,
+    pub relief: Option<i64>,
+    pub top: usize,
+}
 
-enum Operation {
-    Addition(usize),
-    Exponentiation(u32),
-    Multiplication(usize)
+impl Default for Params {
+    fn default() -> Self {
+        Self { rounds: None, relief: None, top: 2 }
+    }
 }
 
-struct Monkey {
-    items: VecDeque<usize>,
-    operation: Operation,
-    divisor: usize,
+impl params::Params for Params {
+    fn example() -> Self {
+        Self::default()
+    }
+
+    fn set(&mut self, name: &str, val: &str) -> Result<(), String> {
+        match name {
+            "rounds" => self.rounds = Some(value(val, "a number")?),
+            "relief" => {
+                let relief = value(val, "a positive integer")?;
+
+                if relief < 1 {
+                    return Err("a positive integer".to_string());
+                }
+
+                self.relief = Some(relief);
+            },
+            "top" => self.top = value(val, "a number")?,
+            _ => return Err("one of `rounds`, `relief` or `top`".to_string()),
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Monkey {
+    items: VecDeque<i64>,
+    // of `old`, the worry level being inspected
+    operation: Expr,
+    divisor: i64,
     success: usize,
     failure: usize,
     inspections: usize
 }
 
 impl Monkey {
-    fn from(input: &str) -> Self {
-        let parts = input
-            .split(['\n', ':'])
-            .collect::<Vec<_>>();
-
-        let items = parts[3]
-            .split(',')
-            .map(|item| item.trim().parse::<usize>().unwrap())
-            .collect::<VecDeque<_>>();
-
-        let mut operation_parts = parts[5].trim().split(' ');
-        let operator = operation_parts.nth(3).unwrap();
-        let operand = operation_parts.next().unwrap();
-
-        let operation = match operator {
-            "*" => match operand {
-                "old" => {
-                    Operation::Exponentiation(2)
-                },
-                multiplier => {
-                    Operation::Multiplication(multiplier.parse().unwrap())
-                }
-            },
-            _ => Operation::Addition(operand.parse().unwrap())
+    // `block` is a slice of `input`, which is used to report error positions
+    fn parse(input: &str, block: &str) -> Result<Self, Error> {
+        let mut lines = block.lines();
+
+        field(input, block, &mut lines, "Monkey ")?;
+
+        let items = field(input, block, &mut lines, "Starting items: ")?
+            .split(", ")
+            .map(|item| number(input, item))
+            .collect::<Result<VecDeque<_>, _>>()?;
+
+        let operation = field(input, block, &mut lines, "Operation: new = ")?;
+        let operation = expression(input, operation)?;
+
+        let divisor = field(input, block, &mut lines, "Test: divisible by ")?;
+        let success = field(input, block, &mut lines, "If true: throw to monkey ")?;
+        let failure = field(input, block, &mut lines, "If false: throw to monkey ")?;
+
+        let divisor = match number(input, divisor)? {
+            0 => return Err(Error::parse(DAY, input, divisor, "a divisor other than 0")),
+            n => n,
         };
 
-        let divisor: usize = parts[7]
-            .split(' ')
-            .last()
-            .unwrap()
-            .parse()
-            .unwrap();
+        Ok(Self {
+            items,
+            operation,
+            divisor,
+            success: number(input, success)?,
+            failure: number(input, failure)?,
+            inspections: 0
+        })
+    }
 
-        let success: usize = parts[9]
-            .split(' ')
-            .last()
-            .unwrap()
-            .parse()
-            .unwrap();
+    pub fn items(&self) -> impl Iterator<Item = i64> + '_ {
+        self.items.iter().copied()
+    }
 
-        let failure: usize = parts[11]
-            .split(' ')
-            .last()
-            .unwrap()
+    pub fn operation(&self) -> &Expr {
+        &self.operation
+    }
+
+    pub fn inspections(&self) -> usize {
+        self.inspections
+    }
+}
+
+// next line of a monkey description with `prefix` stripped
+fn field<'a>(
+    input: &str,
+    block: &'a str,
+    lines: &mut Lines<'a>,
+    prefix: &str
+) -> Result<&'a str, Error> {
+    let expected = format!("`{}`", prefix.trim());
+    let line = lines
+        .next()
+        .ok_or_else(|| Error::parse(DAY, input, end_of(block), &expected))?
+        .trim();
+
+    line
+        .strip_prefix(prefix)
+        .ok_or_else(|| Error::parse(DAY, input, line, &expected))
+}
+
+fn number<T: FromStr>(input: &str, s: &str) -> Result<T, Error> {
+    s.parse().map_err(|_| Error::parse(DAY, input, s, "a number"))
+}
+
+type Tokens<'a> = Peekable<SplitWhitespace<'a>>;
+
+// `old` and numbers, separated by spaces and combined with `+`, `-`, `*` and
+// `/` with the usual precedence. `s` is a slice of `input`.
+fn expression(input: &str, s: &str) -> Result<Expr, Error> {
+    let mut tokens = s.split_whitespace().peekable();
+    let expr = binary(input, s, &mut tokens, 0)?;
+
+    match tokens.next() {
+        Some(token) => Err(Error::parse(DAY, input, token, "an operator")),
+        None => Ok(expr),
+    }
+}
+
+// operators of `level` and above: 0 for `+` and `-`, 1 for `*` and `/`
+fn binary(
+    input: &str,
+    s: &str,
+    tokens: &mut Tokens,
+    level: usize
+) -> Result<Expr, Error> {
+    let operand = |tokens: &mut Tokens| match level {
+        0 => binary(input, s, tokens, 1),
+        _ => operand(input, s, tokens),
+    };
+
+    let symbols = if level == 0 { ["+", "-"] } else { ["*", "/"] };
+    let mut expr = operand(tokens)?;
+
+    while let Some(symbol) = tokens.next_if(|token| symbols.contains(token)) {
+        let operator = Operator::from_symbol(symbol).unwrap();
+        expr = Expr::binary(operator, expr, operand(tokens)?);
+    }
+
+    Ok(expr)
+}
+
+fn operand(input: &str, s: &str, tokens: &mut Tokens) -> Result<Expr, Error> {
+    let expected = "`old` or a number";
+
+    match tokens.next() {
+        Some("old") => Ok(Expr::Variable("old".to_string())),
+        Some(token) => token
             .parse()
-            .unwrap();
+            .map(Expr::Number)
+            .map_err(|_| Error::parse(DAY, input, token, expected)),
+        None => Err(Error::parse(DAY, input, end_of(s), expected)),
+    }
+}
+
+// integer arithmetic, failing on overflow and division by zero
+fn apply(expr: &Expr, old: i64) -> Option<i64> {
+    match expr {
+        Expr::Number(n) => Some(*n),
+        Expr::Variable(_) => Some(old),
+        Expr::Binary(operator, left, right) => {
+            let (l, r) = (apply(left, old)?, apply(right, old)?);
+
+            match operator {
+                Operator::Add => l.checked_add(r),
+                Operator::Sub => l.checked_sub(r),
+                Operator::Mul => l.checked_mul(r),
+                Operator::Div => l.checked_div_euclid(r),
+            }
+        },
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
 
-        Self { items, operation, divisor, success, failure, inspections: 0 }
+fn divides(expr: &Expr) -> bool {
+    match expr {
+        Expr::Binary(Operator::Div, _, _) => true,
+        Expr::Binary(_, left, right) => divides(left) || divides(right),
+        _ => false,
     }
 }
 
-struct Troop {
+pub struct Troop {
     monkeys: Vec<Monkey>,
+    // the lcm of the divisors, if it fits
+    lcm: Option<i64>,
 }
 
 impl Troop {
-    fn from(input: &str) -> Self {
+    pub fn parse(input: &str) -> Result<Self, Error> {
         let monkeys = input
+            .trim_end()
             .split("\n\n")
-            .map(Monkey::from)
-            .collect();
+            .map(|block| Monkey::parse(input, block))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for monkey in &monkeys {
+            for target in [monkey.success, monkey.failure] {
+                if target >= monkeys.len() {
+                    let reason = format!("no monkey {} to throw to", target);
+                    return Err(Error::no_solution(DAY, &reason));
+                }
+            }
+        }
+
+        let lcm = monkeys
+            .iter()
+            .try_fold(1, |lcm: i64, monkey| {
+                let divisor = monkey.divisor.checked_abs()?;
+                (lcm / gcd(lcm, divisor)).checked_mul(divisor)
+            });
 
-        Self { monkeys }
+        Ok(Self { monkeys, lcm })
     }
 
-    fn play_round(&mut self, worry_divisor: Option<usize>) {
+    pub fn monkeys(&self) -> &[Monkey] {
+        &self.monkeys
+    }
+
+    // plays one round, returning how many items each monkey inspected
+    pub fn play_round(&mut self, relief: i64) -> Result<Vec<usize>, Error> {
         let monkeys = &mut self.monkeys;
-        let modulo: usize = monkeys
-            .iter()
-            .map(|monkey| monkey.divisor)
-            .product();
 
-        for i in 0..monkeys.len() {
+        // worry levels only matter modulo every divisor, as long as they are
+        // never divided
+        let divided = monkeys.iter().any(|monkey| divides(&monkey.operation));
+        let modulo = if relief == 1 && !divided {
+            let lcm = self.lcm.ok_or_else(|| {
+                Error::no_solution(DAY, "the divisors' lcm is too large")
+            })?;
+
+            Some(lcm)
+        } else {
+            None
+        };
 
-            while let Some(mut item) = monkeys[i].items.pop_front() {
+        let mut inspections = vec![0; monkeys.len()];
 
-                item = match monkeys[i].operation {
-                    Operation::Addition(addend) => item + addend,
-                    Operation::Exponentiation(exponent) => item.pow(exponent),
-                    Operation::Multiplication(multiplier) => item * multiplier,
-                };
+        for i in 0..monkeys.len() {
 
-                if let Some(divisor) = worry_divisor {
-                    item /= divisor
-                };
+            while let Some(item) = monkeys[i].items.pop_front() {
 
-                item %= modulo;
+                let mut item = apply(&monkeys[i].operation, item)
+                    .ok_or_else(|| {
+                        let reason = format!("monkey {} can't work out a worry level", i);
+                        Error::no_solution(DAY, &reason)
+                    })?
+                    .div_euclid(relief);
+
+                if let Some(modulo) = modulo {
+                    item = item.rem_euclid(modulo);
+                }
 
                 let target = if item % monkeys[i].divisor == 0 {
                     monkeys[i].success
@@ -112,11 +287,23 @@ impl Troop {
 
                 monkeys[target].items.push_back(item);
                 monkeys[i].inspections += 1;
+                inspections[i] += 1;
             }
         }
+
+        Ok(inspections)
     }
 
-    fn monkey_business(&self) -> usize {
+    // the inspections of each round in turn, for as long as the monkeys play
+    pub fn rounds(
+        &mut self,
+        relief: i64
+    ) -> impl Iterator<Item = Result<Vec<usize>, Error>> + '_ {
+        std::iter::from_fn(move || Some(self.play_round(relief)))
+    }
+
+    // the inspection counts of the `top` most active monkeys multiplied
+    pub fn monkey_business(&self, top: usize) -> usize {
         let mut inspections: Vec<_> = self
             .monkeys
             .iter()
@@ -124,35 +311,56 @@ impl Troop {
             .collect();
 
         inspections.sort_by(|a, b| b.cmp(a));
-        inspections.truncate(2);
+        inspections.truncate(top);
 
         inspections.iter().product()
     }
 }
 
-pub fn part1(input: &str) -> usize {
-    let mut troop = Troop::from(input);
+fn play(input: &str, rounds: usize, relief: i64, top: usize) -> Result<usize, Error> {
+    let mut troop = Troop::parse(input)?;
 
-    for _ in  0..20 { 
-        troop.play_round(Some(3));
+    for round in troop.rounds(relief).take(rounds) {
+        round?;
     }
 
-    troop.monkey_business()
+    Ok(troop.monkey_business(top))
 }
 
-pub fn part2(input: &str) -> usize {
-    let mut troop = Troop::from(input);
+pub fn try_part1_with(input: &str, params: &Params) -> Result<usize, Error> {
+    let rounds = params.rounds.unwrap_or(20);
+    let relief = params.relief.unwrap_or(3);
 
-    for _ in  0..10000 { 
-        troop.play_round(None);
-    }
+    play(input, rounds, relief, params.top)
+}
+
+pub fn try_part2_with(input: &str, params: &Params) -> Result<usize, Error> {
+    let rounds = params.rounds.unwrap_or(10000);
+    let relief = params.relief.unwrap_or(1);
+
+    play(input, rounds, relief, params.top)
+}
+
+pub fn try_part1(input: &str) -> Result<usize, Error> {
+    try_part1_with(input, &Params::default())
+}
+
+pub fn try_part2(input: &str) -> Result<usize, Error> {
+    try_part2_with(input, &Params::default())
+}
+
+pub fn part1(input: &str) -> usize {
+    try_part1(input).unwrap()
+}
 
-    troop.monkey_business()
+pub fn part2(input: &str) -> usize {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, try_part1, try_part2, try_part2_with, Params, Troop};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "Monkey 0:\n\
                                 Starting items: 79, 98\n\
@@ -191,5 +399,123 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 2713310158);
     }
-}
 
+    #[test]
+    fn test_parse_error() {
+        let truncated = &TEST_INPUT[..TEST_INPUT.find("Test: divisible by 13").unwrap()];
+        assert_eq!(
+            try_part1(truncated),
+            Err(Error::Parse {
+                day: 11,
+                line: 17,
+                column: 27,
+                expected: "`Test: divisible by`".to_string()
+            })
+        );
+
+        let malformed = TEST_INPUT.replace("old + 6", "old % 6");
+        assert_eq!(
+            try_part1(&malformed),
+            Err(Error::Parse {
+                day: 11,
+                line: 10,
+                column: 22,
+                expected: "an operator".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_operation() {
+        let troop = Troop::parse(TEST_INPUT).unwrap();
+        let operations: Vec<String> = troop
+            .monkeys()
+            .iter()
+            .map(|monkey| monkey.operation().to_string())
+            .collect();
+
+        assert_eq!(operations, ["(old * 19)", "(old + 6)", "(old * old)", "(old + 3)"]);
+
+        let input = TEST_INPUT.replace("old * 19", "2 * old - old / 4 * 3 + 1");
+        let troop = Troop::parse(&input).unwrap();
+
+        assert_eq!(
+            troop.monkeys()[0].operation().to_string(),
+            "(((2 * old) - ((old / 4) * 3)) + 1)"
+        );
+
+        let dangling = TEST_INPUT.replace("old + 6", "old +");
+        assert_eq!(
+            try_part1(&dangling),
+            Err(Error::Parse {
+                day: 11,
+                line: 10,
+                column: 23,
+                expected: "`old` or a number".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_rounds() {
+        let mut troop = Troop::parse(TEST_INPUT).unwrap();
+        let first: Vec<Vec<usize>> = troop
+            .rounds(3)
+            .take(2)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(first[0], [2, 4, 3, 5]);
+        assert_eq!(troop.monkeys()[0].items().collect::<Vec<_>>(), [695, 10, 71, 135, 350]);
+
+        let mut troop = Troop::parse(TEST_INPUT).unwrap();
+        let totals = troop
+            .rounds(1)
+            .take(20)
+            .fold(vec![0; 4], |totals, round| {
+                totals.iter().zip(round.unwrap()).map(|(a, b)| a + b).collect()
+            });
+
+        assert_eq!(totals, [99, 97, 8, 103]);
+        assert_eq!(troop.monkey_business(3), 103 * 99 * 97);
+
+        let params = Params { rounds: Some(20), relief: None, top: 1 };
+        assert_eq!(try_part2_with(TEST_INPUT, &params), Ok(103));
+    }
+
+    #[test]
+    fn test_divisors() {
+        let troop = |divisors: &[i64]| -> String {
+            let monkeys: Vec<String> = divisors
+                .iter()
+                .enumerate()
+                .map(|(i, divisor)| {
+                    format!(
+                        "Monkey {}:\n\
+                         Starting items: {}\n\
+                         Operation: new = old * 2 + 1\n\
+                         Test: divisible by {}\n\
+                         If true: throw to monkey {}\n\
+                         If false: throw to monkey {}\n",
+                        i, i + 2, divisor, (i + 1) % divisors.len(), (i + 2) % divisors.len()
+                    )
+                })
+                .collect();
+
+            monkeys.join("\n")
+        };
+
+        // the product of these overflows, their lcm doesn't
+        let repeated = troop(&[9973; 10]);
+        assert!(try_part2_with(&repeated, &Params::default()).is_ok());
+
+        let primes = [9973, 9967, 9949, 9941, 9931, 9929];
+        assert_eq!(
+            try_part2(&troop(&primes)),
+            Err(Error::no_solution(11, "the divisors' lcm is too large"))
+        );
+
+        // without relief by modulo, part 1 doesn't need the lcm
+        assert!(try_part1(&troop(&primes)).is_ok());
+    }
+}
@@ -1,38 +1,52 @@
-use std::collections::VecDeque;
-
-#[derive(Clone,Copy)]
-struct Node {
-    x: usize,
-    y: usize,
-    d: usize,
-    val: u8
-}
+use crate::error::{end_of, Error};
+use crate::grid::Grid;
+use crate::pathfinding::{self, Path};
+
+const DAY: u8 = 12;
 
-struct Matrix<'a> {
-    rows: Vec<&'a [u8]>
+struct Matrix {
+    grid: Grid<u8>
 }
 
-impl<'a> Matrix<'a> {
-    fn new(input: &'a str) -> Self {
-        let rows: Vec<_> = input
-            .trim()
-            .as_bytes()
-            .split(|&b| b == b'\n')
-            .collect();
+impl Matrix {
+    // a rectangle of heights, with exactly one `S` and one `E`
+    fn parse(input: &str) -> Result<Self, Error> {
+        let rows: Vec<&str> = input.trim().lines().collect();
+        let width = rows.first().map_or(0, |row| row.len());
 
-        Self { rows }
-    }
+        for row in &rows {
+            let height = |c: char| c.is_ascii_lowercase() || c == 'S' || c == 'E';
+
+            if let Some(i) = row.find(|c: char| !height(c)) {
+                return Err(Error::parse(DAY, input, &row[i..], "a height, `S` or `E`"));
+            }
+
+            if row.len() != width {
+                let at = row.get(width..).unwrap_or(end_of(row));
+                return Err(Error::parse(DAY, input, at, "rows of equal length"));
+            }
+        }
+
+        for (square, expected) in [('S', "one start `S`"), ('E', "one end `E`")] {
+            if input.matches(square).count() != 1 {
+                return Err(Error::parse(DAY, input, end_of(input), expected));
+            }
+        }
 
-    fn rows(&self) -> usize {
-        self.rows.len()
+        Ok(Self { grid: Grid::parse(input.trim()) })
     }
 
-    fn cols(&self) -> usize {
-        if let Some(row) = self.rows.first() {
-            return row.len();
+    fn height(&self, pos: (usize, usize)) -> u8 {
+        match self.grid[pos] {
+            b'S' => b'a',
+            b'E' => b'z',
+            val => val
         }
+    }
 
-        0
+    // `parse` checks that there is exactly one of each
+    fn find(&self, square: u8) -> (usize, usize) {
+        self.grid.position(|&val| val == square).unwrap()
     }
 
     fn bfs<F,G>(
@@ -40,158 +54,65 @@ impl<'a> Matrix<'a> {
         s: (usize, usize),
         is_goal: F,
         is_valid: G
-    ) -> Result<usize, ()>
-    where F: Fn(Node) -> bool, G: Fn(Node, Node) -> bool {
-
-        let root = Node { x: s.0, y: s.1, d: 0, val: self.rows[s.1][s.0] };
-
-        let mut visited = vec![vec![false; self.cols()]; self.rows()];
-        visited[root.y][root.x] = true;
-
-        let mut queue: VecDeque<Node> = VecDeque::new();
-        queue.push_back(root);
+    ) -> Option<Path<(usize, usize)>>
+    where F: Fn((usize, usize)) -> bool, G: Fn(u8, u8) -> bool {
 
-        while let Some(node) = queue.pop_front() {
-            if is_goal(node) {
-                return Ok(node.d);
-            }
+        let neighbours = |&pos: &(usize, usize)| {
+            let h = self.height(pos);
 
-            if node.x > 0 {
-                let left = Node {
-                    x: node.x - 1,
-                    y: node.y,
-                    d: node.d + 1,
-                    val: self.rows[node.y][node.x - 1]
-                };
-
-                if !visited[left.y][left.x] && is_valid(node, left) {
-                    queue.push_back(left);
-                    visited[left.y][left.x] = true;
-                }
-            }
+            self.grid
+                .neighbours4(pos)
+                .filter(|&next| is_valid(h, self.height(next)))
+                .collect::<Vec<_>>()
+        };
 
-            if node.x < self.cols() - 1 {
-                let right = Node {
-                    x: node.x + 1,
-                    y: node.y,
-                    d: node.d + 1,
-                    val: self.rows[node.y][node.x + 1],
-                };
-
-                if !visited[right.y][right.x] && is_valid(node, right) {
-                    queue.push_back(right);
-                    visited[right.y][right.x] = true;
-                }
-            }
-
-            if node.y > 0 {
-                let up = Node {
-                    x: node.x,
-                    y: node.y - 1,
-                    d: node.d + 1,
-                    val: self.rows[node.y - 1][node.x],
-                };
-
-                if !visited[up.y][up.x] && is_valid(node, up) {
-                    queue.push_back(up);
-                    visited[up.y][up.x] = true;
-                }
-            }
-
-            if node.y < self.rows() - 1 {
-                let down = Node {
-                    x: node.x,
-                    y: node.y + 1,
-                    d: node.d + 1,
-                    val: self.rows[node.y + 1][node.x],
-                };
-
-                if !visited[down.y][down.x] && is_valid(node, down) {
-                    queue.push_back(down);
-                    visited[down.y][down.x] = true;
-                }
-            }
-        }
-
-        Err(())
+        pathfinding::bfs(s, neighbours, |&pos| is_goal(pos))
     }
 }
 
-pub fn part1(input: &str) -> usize {
-    let terrain = Matrix::new(input);
-
-    let s = terrain
-        .rows
-        .iter()
-        .enumerate()
-        .find_map(|(y, row)| {
-            for (x, &square) in row.iter().enumerate() {
-                if square == b'S' {
-                    return Some((x, y));
-                }
-            }
+pub fn try_part1(input: &str) -> Result<usize, Error> {
+    let terrain = Matrix::parse(input)?;
 
-            None
-        })
-        .unwrap();
+    let s = terrain.find(b'S');
 
     // game rules
-    let height = |node: Node| match node.val {
-        b'S' => b'a',
-        b'E' => b'z',
-        _ => node.val
-    };
-
-    let is_goal = |node: Node| node.val == b'E';
-
-    let is_valid = |node: Node, next: Node| {
-        let (h, n) = (height(node), height(next));
+    let is_goal = |pos| terrain.grid[pos] == b'E';
+    let is_valid = |h: u8, n: u8| h >= n || h.abs_diff(n) <= 1;
 
-        h >= n || h.abs_diff(n) <= 1
-    };
-
-    terrain.bfs(s, is_goal, is_valid).unwrap()
+    terrain
+        .bfs(s, is_goal, is_valid)
+        .map(|path| path.cost)
+        .ok_or_else(|| Error::no_solution(DAY, "no path from `S` to `E`"))
 }
 
-pub fn part2(input: &str) -> usize {
-    let terrain = Matrix::new(input);
-
-    let s = terrain
-        .rows
-        .iter()
-        .enumerate()
-        .find_map(|(y, row)| {
-            for (x, &square) in row.iter().enumerate() {
-                if square == b'E' {
-                    return Some((x, y));
-                }
-            }
+pub fn try_part2(input: &str) -> Result<usize, Error> {
+    let terrain = Matrix::parse(input)?;
 
-            None
-        })
-        .unwrap();
+    // walk downhill from the end to the nearest lowest square
+    let s = terrain.find(b'E');
 
     // game rules
-    let height = |node: Node| match node.val {
-        b'S' => b'a',
-        b'E' => b'z',
-        _ => node.val
-    };
-
-    let is_goal = |node: Node| height(node) == b'a';
+    let is_goal = |pos| terrain.height(pos) == b'a';
+    let is_valid = |h: u8, n: u8| h <= n || h.abs_diff(n) <= 1;
 
-    let is_valid = |node: Node, next: Node| {
-        let (h, n) = (height(node), height(next));
+    terrain
+        .bfs(s, is_goal, is_valid)
+        .map(|path| path.cost)
+        .ok_or_else(|| Error::no_solution(DAY, "no path from any `a` to `E`"))
+}
 
-        h <= n || h.abs_diff(n) <= 1
-    };
+pub fn part1(input: &str) -> usize {
+    try_part1(input).unwrap()
+}
 
-    terrain.bfs(s, is_goal, is_valid).unwrap()
+pub fn part2(input: &str) -> usize {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, try_part1};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "Sabqponm\n\
                               abcryxxl\n\
@@ -208,5 +129,21 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 29);
     }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            try_part1("Sab\nab1\nEaa\n"),
+            Err(Error::Parse {
+                day: 12,
+                line: 2,
+                column: 3,
+                expected: "a height, `S` or `E`".to_string()
+            })
+        );
+        assert!(try_part1("Sab\naaE\naa\n").is_err());
+        assert!(try_part1("Sab\naaa\n").is_err());
+        assert_eq!(try_part1("Saz\nzzE\n"), Err(Error::no_solution(12, "no path from `S` to `E`")));
+    }
 }
 
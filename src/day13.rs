@@ -1,12 +1,92 @@
 use core::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{end_of, Error};
+use crate::params;
+
+const DAY: u8 = 13;
+
+// a packet, or part of one, e.g. `packet![1, [2, 3], []]` for `[1,[2,3],[]]`
+#[macro_export]
+macro_rules! packet {
+    (@item [$($item:tt),* $(,)?]) => {
+        $crate::packet![$($item),*]
+    };
+    (@item $value:expr) => {
+        $crate::day13::ListItem::Value($value)
+    };
+    ($($item:tt),* $(,)?) => {
+        $crate::day13::ListItem::List(vec![$($crate::packet!(@item $item)),*])
+    };
+}
+
+// the divider packets part 2 adds to the packets from the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Params {
+    pub dividers: Vec<ListItem>,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self { dividers: vec![packet![[2]], packet![[6]]] }
+    }
+}
+
+impl params::Params for Params {
+    fn example() -> Self {
+        Self::default()
+    }
+
+    fn set(&mut self, name: &str, val: &str) -> Result<(), String> {
+        match name {
+            "dividers" => {
+                self.dividers = val
+                    .split_whitespace()
+                    .map(|packet| packet.parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| "packets separated by spaces".to_string())?;
+            },
+            _ => return Err("`dividers`".to_string()),
+        }
+
+        Ok(())
+    }
+}
 
 // recursive list
-#[derive(Clone,Eq,PartialEq)]
-enum ListItem {
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub enum ListItem {
     Value(u8),
     List(Vec<ListItem>)
 }
 
+impl ListItem {
+    pub fn value(value: u8) -> Self {
+        ListItem::Value(value)
+    }
+
+    pub fn list<I: IntoIterator<Item = ListItem>>(items: I) -> Self {
+        ListItem::List(items.into_iter().collect())
+    }
+
+    pub fn empty() -> Self {
+        ListItem::List(vec![])
+    }
+}
+
+impl From<u8> for ListItem {
+    fn from(value: u8) -> Self {
+        ListItem::Value(value)
+    }
+}
+
+impl From<Vec<ListItem>> for ListItem {
+    fn from(items: Vec<ListItem>) -> Self {
+        ListItem::List(items)
+    }
+}
+
 // rules for comparing lists
 impl Ord for ListItem {
     fn cmp(&self, other: &Self) -> Ordering {
@@ -68,69 +148,155 @@ impl PartialOrd for ListItem {
     }
 }
 
-// recursive parser
-fn parse_list(input: &str) -> Result<ListItem, ()> {
+impl fmt::Display for ListItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListItem::Value(value) => write!(f, "{}", value),
+            ListItem::List(items) => {
+                write!(f, "[")?;
+
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
 
-    if !input.starts_with('[') && input.ends_with(']') {
-        return Err(());
+                    write!(f, "{}", item)?;
+                }
+
+                write!(f, "]")
+            },
+        }
     }
+}
 
-    // discard outer brackets
-    let input = &input[1..input.len() - 1];
+// where parsing a packet failed, as a byte offset, and what was expected there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseListError {
+    pub offset: usize,
+    pub expected: &'static str,
+}
 
-    if input.is_empty() {
-        return Ok(ListItem::List(vec![]));
+impl fmt::Display for ParseListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {} at offset {}", self.expected, self.offset)
     }
+}
 
-    let mut depth = 0;
-    let mut position = 0;
-    let mut elems: Vec<&str> = vec![];
+impl std::error::Error for ParseListError {}
 
-    for (i, c) in input.chars().enumerate() {
-        match c {
-            '[' => depth += 1,
-            ']' => depth -= 1,
-            _ => ()
+impl FromStr for ListItem {
+    type Err = ParseListError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { s: s.as_bytes(), pos: 0 };
+        let item = parser.item()?;
+
+        if parser.pos < s.len() {
+            return Err(parser.error("the end of the packet"));
         }
-        
-        if c == ',' && depth == 0 {
-            elems.push(&input[position..i]);
-            position = i + 1;
+
+        Ok(item)
+    }
+}
+
+// recursive descent over the bytes of a packet
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, expected: &'static str) -> ParseListError {
+        ParseListError { offset: self.pos, expected }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.pos).copied()
+    }
+
+    fn item(&mut self) -> Result<ListItem, ParseListError> {
+        match self.peek() {
+            Some(b'[') => self.list(),
+            Some(b'0'..=b'9') => self.value(),
+            _ => Err(self.error("a number or list")),
         }
     }
 
-    if position == 0 {
-        elems.push(input);
-    } else {
-        elems.push(&input[position..]);
+    fn value(&mut self) -> Result<ListItem, ParseListError> {
+        let start = self.pos;
+
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+
+        // the digits are ascii, so this is a valid string
+        std::str::from_utf8(&self.s[start..self.pos])
+            .unwrap()
+            .parse()
+            .map(ListItem::Value)
+            .map_err(|_| ParseListError { offset: start, expected: "a number below 256" })
     }
 
-    let mut list: Vec<ListItem> = vec![]; 
+    fn list(&mut self) -> Result<ListItem, ParseListError> {
+        // skip `[`
+        self.pos += 1;
+
+        let mut items = vec![];
 
-    for elem in elems {
-        if elem.starts_with('[') {
-            // recurse if element contains a list
-            list.push(parse_list(elem).unwrap());
-        } else {
-            // base case if element is a value
-            let value = ListItem::Value(elem.parse::<u8>().unwrap());
-            list.push(value);
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(ListItem::List(items));
         }
+
+        loop {
+            items.push(self.item()?);
+
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => break,
+                Some(_) => return Err(self.error("`,` or `]`")),
+                None => return Err(self.error("`]`")),
+            }
+        }
+
+        self.pos += 1;
+
+        Ok(ListItem::List(items))
+    }
+}
+
+// `packet` is a slice of `input`
+fn parse_packet(input: &str, packet: &str) -> Result<ListItem, Error> {
+    if !packet.starts_with('[') {
+        return Err(Error::parse(DAY, input, packet, "`[`"));
     }
 
-    Ok(ListItem::List(list))
+    packet
+        .parse()
+        .map_err(|e: ParseListError| {
+            let at = packet.get(e.offset..).unwrap_or(end_of(packet));
+            Error::parse(DAY, input, at, e.expected)
+        })
 }
 
-pub fn part1(input: &str) -> usize {
-    let packets = input
+fn parse_packets(input: &str) -> Result<Vec<ListItem>, Error> {
+    input
         .lines()
         .filter(|line| !line.is_empty())
-        .map(|packet| parse_list(packet).unwrap())
-        .collect::<Vec<_>>();
+        .map(|packet| parse_packet(input, packet))
+        .collect()
+}
+
+pub fn try_part1_with(input: &str, _params: &Params) -> Result<usize, Error> {
+    let packets = parse_packets(input)?;
+
+    if packets.len() % 2 != 0 {
+        return Err(Error::parse(DAY, input, end_of(input), "a packet"));
+    }
 
     let pairs = packets.chunks(2);
 
-    pairs
+    Ok(pairs
         .enumerate()
         .map(|(i, pair)| {
             if pair[0] < pair[1] {
@@ -139,40 +305,51 @@ pub fn part1(input: &str) -> usize {
                 0
             }
         })
-        .sum()
+        .sum())
 }
 
-pub fn part2(input: &str) -> usize {
-    let mut packets = input
-        .lines()
-        .filter(|line| !line.is_empty())
-        .map(|packet| parse_list(packet).unwrap())
-        .collect::<Vec<_>>();
-
-    let dividers = [
-        parse_list("[[2]]").unwrap(),
-        parse_list("[[6]]").unwrap()
-    ];
-    dividers.iter().for_each(|divider| packets.push(divider.clone()));
-
-    packets.sort();
-
-    packets
+// the product of the dividers' positions once sorted in with the packets
+pub fn decoder_key(packets: &[ListItem], dividers: &[ListItem]) -> usize {
+    dividers
         .iter()
-        .enumerate()
-        .filter_map(|(i, packet)| {
-            if dividers.contains(packet) {
-                Some(i + 1)
-            } else {
-                None
-            }
+        .map(|divider| {
+            let before = packets
+                .iter()
+                .chain(dividers)
+                .filter(|&packet| packet < divider)
+                .count();
+
+            before + 1
         })
         .product()
 }
 
+pub fn try_part2_with(input: &str, params: &Params) -> Result<usize, Error> {
+    let packets = parse_packets(input)?;
+
+    Ok(decoder_key(&packets, &params.dividers))
+}
+
+pub fn try_part1(input: &str) -> Result<usize, Error> {
+    try_part1_with(input, &Params::default())
+}
+
+pub fn try_part2(input: &str) -> Result<usize, Error> {
+    try_part2_with(input, &Params::default())
+}
+
+pub fn part1(input: &str) -> usize {
+    try_part1(input).unwrap()
+}
+
+pub fn part2(input: &str) -> usize {
+    try_part2(input).unwrap()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{decoder_key, part1, part2, try_part1, ListItem, ParseListError};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "[1,1,3,1,1]\n\
                               [1,1,5,1,1]\n\n\
@@ -200,5 +377,66 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 140);
     }
-}
 
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            try_part1("[1,[2,x]]\n[1]\n"),
+            Err(Error::Parse {
+                day: 13,
+                line: 1,
+                column: 7,
+                expected: "a number or list".to_string()
+            })
+        );
+        assert_eq!(
+            try_part1("[1]\n[[1,2]\n"),
+            Err(Error::Parse {
+                day: 13,
+                line: 2,
+                column: 7,
+                expected: "`]`".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_list_item() {
+        for line in TEST_INPUT.lines().filter(|line| !line.is_empty()) {
+            assert_eq!(line.parse::<ListItem>().unwrap().to_string(), line);
+        }
+
+        let packet = packet![1, [2, [3]], []];
+
+        assert_eq!(packet.to_string(), "[1,[2,[3]],[]]");
+        assert_eq!(
+            packet,
+            ListItem::list([
+                ListItem::value(1),
+                ListItem::list([2.into(), vec![3.into()].into()]),
+                ListItem::empty(),
+            ])
+        );
+        assert_eq!("7".parse(), Ok(ListItem::Value(7)));
+
+        let error = |offset, expected| Err(ParseListError { offset, expected });
+
+        assert_eq!("[1,256]".parse::<ListItem>(), error(3, "a number below 256"));
+        assert_eq!("[1 2]".parse::<ListItem>(), error(2, "`,` or `]`"));
+        assert_eq!("[1],".parse::<ListItem>(), error(3, "the end of the packet"));
+        assert_eq!("[,]".parse::<ListItem>(), error(1, "a number or list"));
+    }
+
+    #[test]
+    fn test_decoder_key() {
+        let packets: Vec<ListItem> = TEST_INPUT
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.parse().unwrap())
+            .collect();
+
+        assert_eq!(decoder_key(&packets, &[packet![[2]], packet![[6]]]), 140);
+        assert_eq!(decoder_key(&packets, &[packet![]]), 1);
+        assert_eq!(decoder_key(&packets, &[packet![10]]), 17);
+    }
+}
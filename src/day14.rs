@@ -1,6 +1,10 @@
-const ROWS: usize = 180;
-const COLS: usize = 350;
-const OFFS: usize = 325;
+use crate::error::Error;
+use crate::grid::{Grid, Tile};
+
+const DAY: u8 = 14;
+
+// where the sand pours in from
+const SOURCE: (usize, usize) = (500, 0);
 
 enum Direction {
     Up,
@@ -16,104 +20,175 @@ enum Object {
     Air,
 }
 
+impl Tile for Object {
+    fn to_char(&self) -> char {
+        match self {
+            Object::Rock => '#',
+            Object::Sand => 'o',
+            Object::Air => '.',
+        }
+    }
+}
+
 #[derive(Copy,Clone,Eq,PartialEq)]
 struct Coord {
     x: usize,
     y: usize 
 }
 
+impl Coord {
+    // `coord` is a slice of `input`
+    fn parse(input: &str, coord: &str) -> Result<Self, Error> {
+        let (x, y) = coord
+            .split_once(',')
+            .ok_or_else(|| Error::parse(DAY, input, coord, "`x,y`"))?;
+
+        let x = x
+            .parse::<usize>()
+            .map_err(|_| Error::parse(DAY, input, x, "x coordinate"))?;
+
+        let y = y
+            .parse::<usize>()
+            .map_err(|_| Error::parse(DAY, input, y, "y coordinate"))?;
+
+        Ok(Coord { x, y })
+    }
+}
+
 struct Cave {
-    grid: [[Object; COLS]; ROWS]
+    grid: Grid<Object>,
+    // x coordinate of the grid's first column
+    left: i64,
+    source: Coord,
+    floor: bool,
 }
 
 impl Cave {
-    fn from(input: &str) -> Result<Self, ()> {
-        let mut grid = [[Object::Air; COLS]; ROWS];
-
-        input
+    fn parse(input: &str, source: Coord) -> Result<Self, Error> {
+        let paths = input
             .trim()
             .lines()
-            .for_each(|line| {
+            .map(|line| {
                 line
                     .split(" -> ")
-                    .map(|coord| {
-                        coord
-                            .split_once(',')
-                            .map_or(Err(()), |coord| {
-                                Ok(Coord {
-                                    x: coord.0.parse::<usize>().unwrap() - OFFS,
-                                    y: coord.1.parse::<usize>().unwrap(),
-                                })
-                            })
-                            .unwrap()
-                    })
-                    .into_iter()
-                    .collect::<Vec<_>>()
-                    .windows(2)
-                    .for_each(|line_segment| {
-                        let (mut from, to) = (
-                            line_segment[0],
-                            line_segment[1]
-                        );
-
-                        let direction = if from.x < to.x {
-                            Direction::Right
-                        } else if from.x > to.x {
-                            Direction::Left
-                        } else if from.y < to.y {
-                            Direction::Down
-                        } else {
-                            Direction::Up
-                        };
-
-                        grid[from.y][from.x] = Object::Rock;
-
-                        while from != to {
-                            match &direction {
-                                Direction::Up => from.y -= 1,
-                                Direction::Down => from.y += 1,
-                                Direction::Left => from.x -= 1,
-                                Direction::Right => from.x += 1
-                            }
-
-                            grid[from.y][from.x] = Object::Rock;
+                    .map(|coord| Coord::parse(input, coord))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let coords = paths.iter().flatten().chain([&source]);
+
+        let min_x = coords.clone().map(|coord| coord.x).min().unwrap();
+        let max_x = coords.clone().map(|coord| coord.x).max().unwrap();
+        let max_y = coords.map(|coord| coord.y).max().unwrap();
+
+        // keep a free column on either side and an empty row at the bottom,
+        // so that sand leaving the bounding box falls into the abyss
+        let left = min_x as i64 - 1;
+        let width = max_x - min_x + 3;
+        let height = max_y + 2;
+
+        let mut cave = Self {
+            grid: Grid::new(width, height, Object::Air),
+            left,
+            source,
+            floor: false,
+        };
+
+        for path in &paths {
+            path
+                .windows(2)
+                .for_each(|line_segment| {
+                    let (mut from, to) = (
+                        line_segment[0],
+                        line_segment[1]
+                    );
+
+                    let direction = if from.x < to.x {
+                        Direction::Right
+                    } else if from.x > to.x {
+                        Direction::Left
+                    } else if from.y < to.y {
+                        Direction::Down
+                    } else {
+                        Direction::Up
+                    };
+
+                    cave.set(from, Object::Rock);
+
+                    while from != to {
+                        match &direction {
+                            Direction::Up => from.y -= 1,
+                            Direction::Down => from.y += 1,
+                            Direction::Left => from.x -= 1,
+                            Direction::Right => from.x += 1
                         }
-                    });
-            });
 
-        Ok(Self { grid })
+                        cave.set(from, Object::Rock);
+                    }
+                });
+        }
+
+        Ok(cave)
+    }
+
+    fn set(&mut self, coord: Coord, object: Object) {
+        self.grid[((coord.x as i64 - self.left) as usize, coord.y)] = object;
     }
 
     fn add_floor(&mut self) {
-        let mut floor_y = 0;
+        // the bottom row is empty, the floor goes just below it
+        self.grid.push_row(Object::Rock);
+        self.floor = true;
+    }
 
-        for (y, row) in self.grid.iter().enumerate().rev() {
-            if row.contains(&Object::Rock) {
-                floor_y = y + 2;
-                break;
-            }
-        }
+    // make room for sand piling up against the floor beyond the sides
+    fn widen(&mut self) {
+        let columns = self.grid.height();
+        let floor = self.grid.height() - 1;
 
-        self.grid[floor_y] = [Object::Rock; COLS];
+        self.grid.widen(columns, columns, Object::Air);
+        self.grid.row_mut(floor).fill(Object::Rock);
+        self.left -= columns as i64;
     }
 
     fn fill_with_sand(&mut self) -> usize {
         let mut grains = 0;
 
         loop {
-            let mut grain = Coord { x: 500 - OFFS, y: 0 };
+            let mut grain = Coord {
+                x: (self.source.x as i64 - self.left) as usize,
+                y: self.source.y
+            };
+
+            // a grain on the source means the source is blocked
+            if self.grid[(grain.x, grain.y)] != Object::Air {
+                break;
+            }
 
             loop {
-                if grain.y == self.grid.len() - 1 {
+                if grain.y == self.grid.height() - 1 {
                     break;
                 }
 
-                if self.grid[grain.y + 1][grain.x] == Object::Air {
+                if grain.x == 0 || grain.x == self.grid.width() - 1 {
+                    if !self.floor {
+                        // nothing beyond the rocks to stop it
+                        grain.y = self.grid.height() - 1;
+                        break;
+                    }
+
+                    let left = self.left;
+                    self.widen();
+                    grain.x += (left - self.left) as usize;
+                }
+
+                if self.grid[(grain.x, grain.y + 1)] == Object::Air {
                     grain.y += 1;
-                } else if self.grid[grain.y + 1][grain.x - 1] == Object::Air {
+                } else if self.grid[(grain.x - 1, grain.y + 1)] == Object::Air {
                     grain.y += 1; 
                     grain.x -= 1; 
-                } else if self.grid[grain.y + 1][grain.x + 1] == Object::Air {
+                } else if self.grid[(grain.x + 1, grain.y + 1)] == Object::Air {
                     grain.y += 1; 
                     grain.x += 1; 
                 } else {
@@ -122,40 +197,50 @@ impl Cave {
             }
 
             // sand has reached abyss
-            if grain.y == self.grid.len() - 1 {
+            if grain.y == self.grid.height() - 1 {
                 break;
             }
 
-            self.grid[grain.y][grain.x] = Object::Sand;
+            self.grid[(grain.x, grain.y)] = Object::Sand;
             grains += 1;
-
-            // cave is full
-            if grain.y == 0 { 
-                break;
-            }
-
         }
 
         grains
     }
 }
 
-pub fn part1(input: &str) -> usize {
-    let mut cave = Cave::from(input).unwrap();
+// units of sand that come to rest before sand flows into the abyss or, with
+// a floor, before the source is blocked
+pub fn sand(input: &str, source: (usize, usize), floor: bool) -> Result<usize, Error> {
+    let mut cave = Cave::parse(input, Coord { x: source.0, y: source.1 })?;
+
+    if floor {
+        cave.add_floor();
+    }
 
-    cave.fill_with_sand()
+    Ok(cave.fill_with_sand())
 }
 
-pub fn part2(input: &str) -> usize {
-    let mut cave = Cave::from(input).unwrap();
-    cave.add_floor();
+pub fn try_part1(input: &str) -> Result<usize, Error> {
+    sand(input, SOURCE, false)
+}
 
-    cave.fill_with_sand()
+pub fn try_part2(input: &str) -> Result<usize, Error> {
+    sand(input, SOURCE, true)
+}
+
+pub fn part1(input: &str) -> usize {
+    try_part1(input).unwrap()
+}
+
+pub fn part2(input: &str) -> usize {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, sand, try_part1};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "498,4 -> 498,6 -> 496,6\n\
                               503,4 -> 502,4 -> 502,9 -> 494,9\n";
@@ -169,5 +254,30 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 93);
     }
-}
 
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            try_part1("498,4 -> 498;6\n"),
+            Err(Error::Parse {
+                day: 14,
+                line: 1,
+                column: 10,
+                expected: "`x,y`".to_string()
+            })
+        );
+        assert!(try_part1("498,4 -> -1,4\n").is_err());
+    }
+
+    #[test]
+    fn test_sand() {
+        assert_eq!(sand("500,2 -> 500,2\n", (500, 0), false), Ok(0));
+        assert_eq!(sand("500,2 -> 500,2\n", (500, 0), true), Ok(15));
+
+        // sand stacks up on a wide shelf far from the default source
+        let shelf = "1000,300 -> 3000,300\n";
+
+        assert_eq!(sand(shelf, (2000, 0), false), Ok(90000));
+        assert_eq!(sand(shelf, (2000, 0), true), Ok(90000));
+    }
+}
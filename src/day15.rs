@@ -1,237 +1,163 @@
-#[derive(Clone, Copy, PartialEq)]
-struct Vertex {
-    x: f64,
-    y: f64,
+use crate::error::Error;
+use crate::params::{self, value};
+use crate::Answer;
+
+const DAY: u8 = 15;
+
+// the row to count covered cells in, and the opposite corners of the
+// rectangle to search for the distress beacon
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Params {
+    pub row: i64,
+    pub min: (i64, i64),
+    pub max: (i64, i64),
 }
 
-impl Vertex {
-    fn manhattan_distance(&self, other: Self) -> u64 {
-        (self.x as i64).abs_diff(other.x as i64) + (self.y as i64).abs_diff(other.y as i64)
-    }
+impl Params {
+    pub const PUZZLE: Self = Self { row: 2000000, min: (0, 0), max: (4000000, 4000000) };
+    pub const EXAMPLE: Self = Self { row: 10, min: (0, 0), max: (20, 20) };
 }
 
-#[derive(Clone, Copy)]
-struct LineSegment {
-    slope: f64,
-    constant: f64,
-    x_min: f64,
-    x_max: f64,
+impl Default for Params {
+    fn default() -> Self {
+        Self::PUZZLE
+    }
 }
 
-impl LineSegment {
-    fn from(a: &Vertex, b: &Vertex) -> Self {
-        let slope = (b.y - a.y) / (b.x - a.x);
-        let constant = a.y - slope * a.x;
-        let x_min = a.x.min(b.x);
-        let x_max = a.x.max(b.x);
-
-        Self { slope, constant, x_min, x_max }
+impl params::Params for Params {
+    fn example() -> Self {
+        Self::EXAMPLE
     }
 
-    fn intersect(&self, other: &Self) -> Option<Vertex> {
-        let (mut left, mut right) = (*self, *other);
-
-        // segments with same slope have none or many solutions
-        if left.slope == other.slope {
-            return None;
+    fn set(&mut self, name: &str, val: &str) -> Result<(), String> {
+        match name {
+            "row" => self.row = value(val, "an integer")?,
+            "min" => self.min = corner(val)?,
+            "max" => self.max = corner(val)?,
+            _ => return Err("one of `row`, `min` or `max`".to_string()),
         }
 
-        left.slope -= right.slope;
-        right.slope = 0.0;
-
-        right.constant -= left.constant;
-        left.constant = 0.0;
-
-        let x = right.constant / left.slope;
-    
-        if x < self.x_min || x > self.x_max || x < other.x_min || x > other.x_max {
-            return None;
-        }
+        Ok(())
+    }
+}
 
-        let y = self.y_from_x(x);
+// `x,y`, or a single integer for both
+fn corner(val: &str) -> Result<(i64, i64), String> {
+    let expected = "an integer or `x,y`";
 
-        Some(Vertex { x, y })
+    match val.split_once(',') {
+        Some((x, y)) => Ok((value(x, expected)?, value(y, expected)?)),
+        None => value(val, expected).map(|n| (n, n)),
     }
+}
 
-    fn y_from_x(&self, x: f64) -> f64 {
-        self.slope * x + self.constant
-    }
+fn manhattan_distance(a: (i64, i64), b: (i64, i64)) -> i64 {
+    (a.0 - b.0).abs() + (a.1 - b.1).abs()
+}
+
+// rotating by 45° turns the diamonds covered by sensors into squares: (u, v)
+// = (x + y, x - y), and a cell is only a cell if u and v have equal parity
+fn rotate(pos: (i64, i64)) -> (i64, i64) {
+    (pos.0 + pos.1, pos.0 - pos.1)
 }
 
+// a rectangle in rotated coordinates, with inclusive bounds
 #[derive(Clone, Copy)]
 struct Square {
-    vertices: [Vertex; 4],
+    u: (i64, i64),
+    v: (i64, i64),
 }
 
 impl Square {
     fn from(sensor: &Sensor) -> Self {
-        Self {
-            vertices: [
-                Vertex { 
-                    x: sensor.pos.x,
-                    y: sensor.pos.y - sensor.beacon_distance() as f64 - 1.0,
-                },
-                Vertex {
-                    x: sensor.pos.x + sensor.beacon_distance() as f64 + 1.0,
-                    y: sensor.pos.y
-                },
-                Vertex { 
-                    x: sensor.pos.x,
-                    y: sensor.pos.y + sensor.beacon_distance() as f64 + 1.0,
-                },
-                Vertex {
-                    x: sensor.pos.x - sensor.beacon_distance() as f64 - 1.0,
-                    y: sensor.pos.y
-                },
-            ]
-        }
-    }
-
-    fn contains(&self, vertex: Vertex) -> bool {
-        let line_segments = self.line_segments();
-
-        if line_segments.iter().any(|segment| segment.slope.is_infinite()) {
-            // square is parallel to axes
-            vertex.x >= self.vertices[3].x
-            && vertex.x <= self.vertices[0].x
-            && vertex.y >= self.vertices[0].y
-            && vertex.y <= self.vertices[1].y
-        } else {
-            // square is not parallel to axes
-            vertex.y > line_segments[0].y_from_x(vertex.x)
-            && vertex.y < line_segments[1].y_from_x(vertex.x)
-            && vertex.y < line_segments[2].y_from_x(vertex.x)
-            && vertex.y > line_segments[3].y_from_x(vertex.x)
-        }
-    }
+        let (u, v) = rotate(sensor.pos);
+        let r = sensor.beacon_distance();
 
-    fn line_segments(&self) -> [LineSegment; 4] {
-        [
-            LineSegment::from(&self.vertices[0], &self.vertices[1]),
-            LineSegment::from(&self.vertices[1], &self.vertices[2]),
-            LineSegment::from(&self.vertices[2], &self.vertices[3]),
-            LineSegment::from(&self.vertices[3], &self.vertices[0]),
-        ]
-    }
-
-    fn intersect_line_segment(&self, other: &LineSegment) -> Option<Vec<Vertex>>{
-        let mut intersections = vec![];
-
-        for self_ls in self.line_segments() {
-            if let Some(intersection) = self_ls.intersect(other) {
-                intersections.push(intersection);
-            }
-        }
-
-        if intersections.is_empty() {
-            None
-        } else {
-            Some(intersections)
-        }
+        Self { u: (u - r, u + r), v: (v - r, v + r) }
     }
 
-    fn intersect_square(&self, other: &Self) -> Option<Vec<Vertex>> {
-        let mut intersections = vec![];
-
-        for self_ls in self.line_segments() {
-            for other_ls in other.line_segments() {
-                if let Some(intersection) = self_ls.intersect(&other_ls) {
-                    intersections.push(intersection)
-                }
-            }
-        }
-
-        if intersections.is_empty() {
-            None
-        } else {
-            Some(intersections)
-        }
+    fn contains(&self, (u, v): (i64, i64)) -> bool {
+        (self.u.0..=self.u.1).contains(&u) && (self.v.0..=self.v.1).contains(&v)
     }
 }
 
-struct Beacon {
-    pos: Vertex,
-}
-
 struct Sensor {
-    pos: Vertex,
-    beacon: Beacon,
+    pos: (i64, i64),
+    beacon: (i64, i64),
 }
 
 impl Sensor {
-    fn from(input: &str) -> Self {
-        let mut input = input.split(['=', ',', ':']);
-
-        let pos = Vertex {
-            x: input.nth(1).unwrap().parse::<f64>().unwrap(),
-            y: input.nth(1).unwrap().parse::<f64>().unwrap(),
-        };
-
-        let beacon = Beacon { 
-            pos: Vertex {
-                x: input.nth(1).unwrap().parse::<f64>().unwrap(),
-                y: input.nth(1).unwrap().parse::<f64>().unwrap(),
-            }
-        };
+    // `line` is a slice of `input`
+    fn parse(input: &str, line: &str) -> Result<Self, Error> {
+        let mut rest = line;
+        let mut values = [0i64; 4];
+
+        for (value, prefix) in values.iter_mut().zip([
+            "Sensor at x=",
+            ", y=",
+            ": closest beacon is at x=",
+            ", y=",
+        ]) {
+            rest = rest
+                .strip_prefix(prefix)
+                .ok_or_else(|| {
+                    Error::parse(DAY, input, rest, &format!("`{}`", prefix))
+                })?;
+
+            let len = rest
+                .find(|c: char| c != '-' && !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+
+            *value = rest[..len]
+                .parse::<i64>()
+                .map_err(|_| Error::parse(DAY, input, rest, "an integer"))?;
+
+            rest = &rest[len..];
+        }
+
+        if !rest.is_empty() {
+            return Err(Error::parse(DAY, input, rest, "end of line"));
+        }
 
-        Self { pos, beacon }
+        let pos = (values[0], values[1]);
+        let beacon = (values[2], values[3]);
 
+        Ok(Self { pos, beacon })
     }
 
-    fn beacon_distance(&self) -> u64 {
-        self.pos.manhattan_distance(self.beacon.pos)
+    fn beacon_distance(&self) -> i64 {
+        manhattan_distance(self.pos, self.beacon)
     }
 
-    fn can_see(&self, coord: Vertex) -> bool {
-        self.pos.manhattan_distance(coord) <= self.beacon_distance()
-    }
+    // the cells this sensor covers in row `y`, if any
+    fn row(&self, y: i64) -> Option<(i64, i64)> {
+        let d = self.beacon_distance() - (self.pos.1 - y).abs();
 
+        if d >= 0 {
+            Some((self.pos.0 - d, self.pos.0 + d))
+        } else {
+            None
+        }
+    }
 }
 
-fn day15_part1(input: &str, y: i64) -> usize {
-    let sensors: Vec<Sensor> = input
+fn parse_sensors(input: &str) -> Result<Vec<Sensor>, Error> {
+    input
         .trim()
         .lines()
-        .map(Sensor::from)
-        .collect();
+        .map(|line| Sensor::parse(input, line))
+        .collect()
+}
 
-    let squares: Vec<Square> = sensors
+// cells in row `y` that cannot contain a beacon
+pub fn covered(input: &str, y: i64) -> Result<usize, Error> {
+    let sensors = parse_sensors(input)?;
+
+    let mut ranges: Vec<(i64, i64)> = sensors
         .iter()
-        .map(Square::from)
+        .filter_map(|sensor| sensor.row(y))
         .collect();
 
-    // represent the given y as an infinite line segment
-    let row = LineSegment { 
-        slope: 0.0,
-        constant: y as f64,
-        x_min: -f64::INFINITY,
-        x_max: f64::INFINITY
-    };
-
-
-    // find ranges of occupied y by determining intersections of y with each square
-    let mut ranges: Vec<(i64, i64)> = vec![];
-
-    for square in &squares {
-        if let Some(intersections) = square.intersect_line_segment(&row) {
-            let mut range = (
-                intersections
-                    .iter()
-                    .map(|intersection| intersection.x as i64).min().unwrap(),
-                intersections
-                    .iter()
-                    .map(|intersection| intersection.x as i64).max().unwrap(),
-            );
-
-            if range.0.abs_diff(range.1) != 0 {
-                range.0 += 1;
-                range.1 -= 1;
-            }
-
-            ranges.push(range);
-        }
-    }
-
     ranges.sort_by(|a, b| b.cmp(a));
 
     // join ranges
@@ -239,7 +165,7 @@ fn day15_part1(input: &str, y: i64) -> usize {
 
     while let Some(mut range) = ranges.pop() {
         while let Some(inner_range) = ranges.last() {
-            if range.1 >= inner_range.0 {
+            if range.1 + 1 >= inner_range.0 {
                 range.1 = range.1.max(inner_range.1);
                 ranges.pop();
             } else {
@@ -250,71 +176,137 @@ fn day15_part1(input: &str, y: i64) -> usize {
         joined_ranges.push(range)
     }
 
-    // sum joined ranges
-    joined_ranges
+    let covered: i64 = joined_ranges
         .iter()
-        .map(|joined_range| joined_range.1 - joined_range.0)
-        .sum::<i64>() as usize
-}
+        .map(|joined_range| joined_range.1 - joined_range.0 + 1)
+        .sum();
 
-pub fn day15_part2(input: &str, range: (f64, f64)) -> i64 {
-    let sensors: Vec<Sensor> = input
-        .trim()
-        .lines()
-        .map(Sensor::from)
+    // cells with a beacon can't not contain one
+    let mut beacons: Vec<i64> = sensors
+        .iter()
+        .filter(|sensor| sensor.beacon.1 == y)
+        .map(|sensor| sensor.beacon.0)
         .collect();
 
+    beacons.sort();
+    beacons.dedup();
+
+    Ok((covered - beacons.len() as i64) as usize)
+}
+
+// every cell with `min <= (x, y) <= max` that no sensor covers, ordered by
+// row
+pub fn uncovered(
+    input: &str,
+    min: (i64, i64),
+    max: (i64, i64),
+) -> Result<Vec<(i64, i64)>, Error> {
+    let sensors = parse_sensors(input)?;
+    let ((x0, y0), (x1, y1)) = (min, max);
+
     let squares: Vec<Square> = sensors
         .iter()
         .map(Square::from)
         .collect();
 
-    let range = Square {
-        vertices: [
-            Vertex { x: range.1,  y: range.0 },
-            Vertex { x: range.1,  y: range.1 },
-            Vertex { x: range.0,  y: range.1 },
-            Vertex { x: range.0,  y: range.0 },
-        ]
-    };
-
-    // for each square:
-    // 1. find all intersections with other squares
-    //
-    // for all intersection:
-    // 1. return the first intersection that does not lie inside any square and
-    //    which lies within the requested range
+    // cut the rotated plane along every square's edges (and the search
+    // area's), so that each block is either inside or outside every square
+    let mut us = vec![x0 + y0, x1 + y1 + 1];
+    let mut vs = vec![x0 - y1, x1 - y0 + 1];
+
     for square in &squares {
-        for other_square in &squares {
-            if let Some(intersections) = square.intersect_square(other_square) {
-                for intersection in intersections {
-                    if range.contains(intersection)
-                        && squares
-                            .iter()
-                            .all(|square| !square.contains(intersection)
-                    ) {
-                        let freq = intersection.x * 4000000.0 + intersection.y;
-                        return freq as i64;
-                    }
-                }
+        us.extend([square.u.0, square.u.1 + 1]);
+        vs.extend([square.v.0, square.v.1 + 1]);
+    }
+
+    for cuts in [&mut us, &mut vs] {
+        cuts.sort();
+        cuts.dedup();
+    }
+
+    let mut cells = vec![];
+
+    for u in us.windows(2) {
+        for v in vs.windows(2) {
+            // blocks are half-open: u.0 <= u < u.1
+            if squares.iter().any(|square| square.contains((u[0], v[0]))) {
+                continue;
+            }
+
+            // rows in which the block and the search area share a cell
+            let y_min = (u[0] - v[1] + 2).div_euclid(2)
+                .max(u[0] - x1)
+                .max(x0 - v[1] + 1)
+                .max(y0);
+            let y_max = (u[1] - 1 - v[0]).div_euclid(2)
+                .min(x1 - v[0])
+                .min(u[1] - 1 - x0)
+                .min(y1);
+
+            for y in y_min..=y_max {
+                let x_min = (u[0] - y).max(v[0] + y).max(x0);
+                let x_max = (u[1] - 1 - y).min(v[1] - 1 + y).min(x1);
+
+                cells.extend((x_min..=x_max).map(|x| (x, y)));
             }
         }
     }
 
-    0
+    cells.sort_by_key(|&(x, y)| (y, x));
+
+    Ok(cells)
+}
+
+pub fn try_part1_with(input: &str, params: &Params) -> Result<usize, Error> {
+    covered(input, params.row)
+}
+
+// the distress beacon's tuning frequency if a single cell in the search area
+// is uncovered, otherwise how many are, which is what searching an area other
+// than the puzzle's asks about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Search {
+    Frequency(i64),
+    Uncovered(usize),
+}
+
+impl From<Search> for Answer {
+    fn from(search: Search) -> Self {
+        match search {
+            Search::Frequency(frequency) => Answer::Signed(frequency),
+            Search::Uncovered(count) => Answer::Text(format!("{} uncovered cells", count)),
+        }
+    }
+}
+
+pub fn try_part2_with(input: &str, params: &Params) -> Result<Search, Error> {
+    match uncovered(input, params.min, params.max)?[..] {
+        [(x, y)] => Ok(Search::Frequency(x * 4000000 + y)),
+        ref cells => Ok(Search::Uncovered(cells.len())),
+    }
+}
+
+pub fn try_part1(input: &str) -> Result<usize, Error> {
+    try_part1_with(input, &Params::default())
+}
+
+pub fn try_part2(input: &str) -> Result<Search, Error> {
+    try_part2_with(input, &Params::default())
 }
 
 pub fn part1(input: &str) -> usize {
-    day15_part1(input, 2000000)
+    try_part1(input).unwrap()
 }
 
-pub fn part2(input: &str) -> i64 {
-    day15_part2(input, (0.0, 4000000.0))
+pub fn part2(input: &str) -> Search {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{day15_part1, day15_part2};
+    use super::{covered, try_part1_with, try_part2_with, uncovered, Params, Search};
+    use crate::error::Error;
+    use crate::params::Params as _;
 
     const TEST_INPUT: &str = 
         "Sensor at x=2, y=18: closest beacon is at x=-2, y=15\n\
@@ -334,12 +326,76 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(day15_part1(TEST_INPUT, 10), 26);
+        assert_eq!(try_part1_with(TEST_INPUT, &Params::EXAMPLE), Ok(26));
+        assert_eq!(covered(TEST_INPUT, 11), Ok(28));
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(day15_part2(TEST_INPUT, (0.0, 20.0)), 56000011);
+        assert_eq!(
+            try_part2_with(TEST_INPUT, &Params::EXAMPLE),
+            Ok(Search::Frequency(56000011))
+        );
+
+        // other areas report how much of them is uncovered
+        let input = "Sensor at x=0, y=0: closest beacon is at x=1, y=0\n";
+        let mut params = Params::EXAMPLE;
+
+        params.set("min", "-1").unwrap();
+        params.set("max", "1").unwrap();
+        assert_eq!(try_part2_with(input, &params), Ok(Search::Uncovered(4)));
+
+        params.set("min", "-3,-1").unwrap();
+        params.set("max", "3,-1").unwrap();
+        assert_eq!(params.min, (-3, -1));
+        assert_eq!(try_part2_with(input, &params), Ok(Search::Uncovered(6)));
+
+        params.set("max", "-3,-1").unwrap();
+        assert_eq!(try_part2_with(input, &params), Ok(Search::Frequency(-12000001)));
+
+        assert!(params.set("min", "1,").is_err());
+    }
+
+    #[test]
+    fn test_uncovered() {
+        assert_eq!(uncovered(TEST_INPUT, (0, 0), (20, 20)), Ok(vec![(14, 11)]));
+        assert_eq!(uncovered(TEST_INPUT, (14, 14), (14, 14)), Ok(vec![]));
+
+        // a lone sensor leaves everything beyond its reach uncovered
+        let input = "Sensor at x=0, y=0: closest beacon is at x=1, y=0\n";
+        let cells = uncovered(input, (-1, -1), (1, 1)).unwrap();
+
+        assert_eq!(cells, [(-1, -1), (1, -1), (-1, 1), (1, 1)]);
+        assert_eq!(uncovered(input, (-100, -100), (100, 100)).unwrap().len(), 201 * 201 - 5);
+
+        // and rectangles of any shape
+        let cells = uncovered(input, (-2, -1), (2, 0)).unwrap();
+
+        assert_eq!(cells, [(-2, -1), (-1, -1), (1, -1), (2, -1), (-2, 0), (2, 0)]);
+        assert_eq!(uncovered(input, (-1, -50), (0, 50)).unwrap().len(), 2 * 101 - 4);
+        assert_eq!(uncovered(input, (1, 0), (0, 0)), Ok(vec![]));
+
+        // coordinates too large to be exact as floats
+        let x = 9007199254740993i64;
+        let input = format!(
+            "Sensor at x={}, y={}: closest beacon is at x={}, y={}\n",
+            x, x, x, x + 1
+        );
+
+        assert_eq!(uncovered(&input, (x, x), (x + 1, x + 1)), Ok(vec![(x + 1, x + 1)]));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            covered("Sensor at x=2, y=18: closest beacon is at x=-2\n", 10),
+            Err(Error::Parse {
+                day: 15,
+                line: 1,
+                column: 47,
+                expected: "`, y=`".to_string()
+            })
+        );
     }
 }
 
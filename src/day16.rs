@@ -1,11 +1,15 @@
-#![allow(clippy::too_many_arguments)]
-#![allow(clippy::needless_range_loop)]
-
-use core::cmp::Ordering;
 use std::collections::HashMap;
-use std::collections::HashSet;
-use std::collections::BTreeSet;
-use std::collections::VecDeque;
+
+use crate::error::Error;
+use crate::pathfinding;
+
+const DAY: u8 = 16;
+
+// sets of valves are bitmasks indexing a table, which has to fit in memory
+const MAX_VALVES: usize = 20;
+
+// a distance longer than any time limit
+const UNREACHABLE: usize = usize::MAX / 2;
 
 /* DOT langauge representation of graph
 digraph G {
@@ -46,406 +50,206 @@ digraph G {
 #[derive(Debug,Default,Clone,Eq,PartialEq,Hash)]
 struct Node {
     value: usize,
-    open: bool,
     edges: Vec<String>
 }
 
 impl Node {
-    fn from(line: &str) -> (Self, &str) {
-        let mut parts = line
-            .split([' ', '=', ';', ',']);
-
-        let key = parts.nth(1).unwrap();
-
-        let value = parts.nth(3).unwrap().parse().unwrap();
+    // `line` is a slice of `input`
+    fn parse<'a>(input: &str, line: &'a str) -> Result<(Self, &'a str), Error> {
+        let parts: Vec<&'a str> = line
+            .split([' ', '=', ';', ','])
+            .collect();
+
+        let (key, value) = match parts[..] {
+            ["Valve", key, "has", "flow", "rate", value, "", _, _, "to", _, ..] => (key, value),
+            _ => return Err(Error::parse(DAY, input, line, "a valve and its tunnels")),
+        };
+
+        let value = value
+            .parse()
+            .map_err(|_| Error::parse(DAY, input, value, "a flow rate"))?;
         
         let mut edges = vec![];
 
-        for edge in parts.skip(5) {
+        for edge in &parts[11..] {
             if edge.is_empty() {
                 continue;
             }
 
-            edges.push(edge.to_owned());
+            edges.push(edge.to_string());
         }
 
-        (Self { value, open: false, edges }, key)
+        Ok((Self { value, edges }, key))
     }
 }
 
-impl Ord for Node {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.value.cmp(&other.value)
-    }
+// shortest distances between every pair of valves
+fn distances<'a>(
+    graph: &HashMap<&'a str, Node>
+) -> HashMap<&'a str, HashMap<&'a str, usize>> {
+    graph
+        .keys()
+        .map(|&key| {
+            let neighbours = |node: &&'a str| {
+                graph
+                    .get(node)
+                    .unwrap()
+                    .edges
+                    .iter()
+                    .filter_map(|edge| graph.get_key_value(edge as &str))
+                    .map(|(&edge_key, _)| edge_key)
+                    .collect::<Vec<_>>()
+            };
+
+            (key, pathfinding::bfs_reach(key, neighbours))
+        })
+        .collect()
 }
 
-impl PartialOrd for Node {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
+// the graph reduced to the valves worth opening, which are numbered so that a
+// set of them fits in a bitmask
+struct Valves {
+    rates: Vec<usize>,
+    // minutes from valve i to valve j, or from the start for j = rates.len()
+    distances: Vec<Vec<usize>>,
 }
 
-fn bfs(
-    graph: &HashMap<&str, Node>,
-    root: &str,
-    goal: &str
-) -> Result<usize, ()> {
-    let mut queue: VecDeque<(&str, usize)> = VecDeque::new();
-
-    queue.push_back((root, 0));
+impl Valves {
+    fn parse(input: &str) -> Result<Self, Error> {
+        let graph: HashMap<&str, Node> = input
+            .trim()
+            .lines()
+            .map(|line| Node::parse(input, line).map(|(node, key)| (key, node)))
+            .collect::<Result<_, _>>()?;
 
-    while let Some(node) = queue.pop_front() {
-        if node.0 == goal {
-            return Ok(node.1);
+        if !graph.contains_key("AA") {
+            return Err(Error::no_solution(DAY, "there is no valve AA to start at"));
         }
 
-        for edge_key in graph.get(node.0).unwrap().edges.iter() {
-            if graph.get(edge_key as &str).is_some() {
-                queue.push_back((edge_key, node.1 + 1));
-            }
-        }
-    }
+        let mut keys: Vec<&str> = graph
+            .iter()
+            .filter(|(_, node)| node.value > 0)
+            .map(|(&key, _)| key)
+            .collect();
 
-    Err(())
-}
+        keys.sort();
 
-fn max_flow(
-    graph: &HashMap<&str, Node>,
-    distances: &HashMap<&str, HashMap<&str, usize>>,
-    start: &str,
-    to_visit: HashSet<&str>,
-    minutes: usize,
-    pressure: usize,
-    acc: usize,
-    max: &mut usize
-) {
-     // base case
-    if minutes >= 30 || to_visit.is_empty() {
-        let  total = acc + pressure * (30 - minutes);
-        if total > *max {
-            *max = total;
+        if keys.len() > MAX_VALVES {
+            let reason = format!("more than {} valves have a flow rate", MAX_VALVES);
+            return Err(Error::no_solution(DAY, &reason));
         }
 
-        return;
+        let rates = keys
+            .iter()
+            .map(|key| graph.get(key).unwrap().value)
+            .collect();
+
+        let all = distances(&graph);
+
+        let distances = keys
+            .iter()
+            .chain(["AA"].iter())
+            .map(|from| {
+                keys
+                    .iter()
+                    .map(|to| all[from].get(to).copied().unwrap_or(UNREACHABLE))
+                    .collect()
+            })
+            .collect();
+
+        Ok(Self { rates, distances })
     }
 
-    // recursive case
-    for dst in &to_visit {
-        let node = &graph.get(dst).unwrap();
-
-        let mut remaining = to_visit.clone();
-        remaining.remove(dst);
-
-        let minutes_to_dst = distances.get(start).unwrap().get(dst).unwrap();
-        let new_minutes = (minutes_to_dst + 1).min(30 - minutes);
-
-        max_flow(
-            graph,
-            distances,
-            dst,
-            remaining,
-            minutes + new_minutes,
-            pressure + node.value,
-            acc + new_minutes * pressure,
-            max
-        )
+    fn start(&self) -> usize {
+        self.rates.len()
     }
-}
 
-fn max_flow_with_elephant(
-    graph: &HashMap<&str, Node>,
-    distances: &HashMap<&str, HashMap<&str, usize>>,
-    me: &str,
-    elephant: &str,
-    to_visit: BTreeSet<&str>,
-    minutes_me: usize,
-    minutes_elephant: usize,
-    pressure_me: usize,
-    pressure_elephant: usize,
-    mut ticks: [usize; 26],
-    max: &mut usize
-) {
-     // base case
-    if (minutes_me >= 26 && minutes_elephant >= 26) || to_visit.is_empty() {
-
-        for i in minutes_me..26 {
-            ticks[i] += pressure_me;
-        }
-        for i in minutes_elephant..26 {
-            ticks[i] += pressure_elephant;
-        }
-
-        let total = ticks.iter().sum();
+    // the most pressure that can be released by opening exactly the valves in
+    // each set, starting at AA with `minutes` left
+    fn best_per_set(&self, minutes: usize) -> Vec<usize> {
+        let mut best = vec![0; 1 << self.rates.len()];
 
-        if total > *max {
-            *max = total;
-            println!("ticks: {:?}", ticks);
-            println!("new max: {}", *max);
-        }
+        self.explore(self.start(), minutes, 0, 0, &mut best);
 
-        return;
+        best
     }
 
-    //for dst in to_visit.iter() {
-    if to_visit.len() == 1 {
-        let dst = to_visit.iter().next().unwrap();
-
-        if minutes_me >= 26 && minutes_elephant >= 26 {
-            max_flow_with_elephant(
-                graph,
-                distances,
-                dst,
-                elephant,
-                to_visit.clone(),
-                minutes_me,
-                minutes_elephant,
-                pressure_me,
-                pressure_elephant,
-                ticks,
-                max
-            );
-        }
-
-        let node = &graph.get(dst).unwrap();
-
-        let mut remaining = to_visit.clone();
-        remaining.remove(dst);
-
-        let minutes_to_dst_me = distances
-            .get(me)
-            .unwrap()
-            .get(dst)
-            .unwrap();
-        let new_minutes_me = (minutes_to_dst_me + 1).min(26 - minutes_me);
-
-        let mut new_ticks = ticks;
-        for i in minutes_me..(minutes_me + new_minutes_me) {
-            new_ticks[i] += pressure_me;
-        }
-
-
-        if new_minutes_me > 0 {
-            max_flow_with_elephant(
-                graph,
-                distances,
-                dst,
-                elephant,
-                remaining.clone(),
-                minutes_me + new_minutes_me,
-                minutes_elephant,
-                pressure_me + node.value,
-                pressure_elephant,
-                new_ticks,
-                max
-            );
-        }
-
-        let minutes_to_dst_elephant = distances
-            .get(elephant)
-            .unwrap()
-            .get(dst)
-            .unwrap();
-
-        let new_minutes_elephant = (minutes_to_dst_elephant + 1).min(26 - minutes_elephant);
+    fn explore(
+        &self,
+        at: usize,
+        minutes: usize,
+        opened: usize,
+        pressure: usize,
+        best: &mut [usize]
+    ) {
+        best[opened] = best[opened].max(pressure);
+
+        for (valve, &rate) in self.rates.iter().enumerate() {
+            // walking there and opening it takes a minute more than the walk
+            let cost = self.distances[at][valve] + 1;
+
+            if opened & (1 << valve) != 0 || cost >= minutes {
+                continue;
+            }
 
-        let mut ticks_elephant = ticks;
-        for j in minutes_elephant..(minutes_elephant + new_minutes_elephant) {
-            ticks_elephant[j] += pressure_elephant;
-        }
+            let minutes = minutes - cost;
 
-        if new_minutes_elephant > 0 {
-            max_flow_with_elephant(
-                graph,
-                distances,
-                me,
-                dst,
-                remaining,
-                minutes_me,
-                minutes_elephant + new_minutes_elephant,
-                pressure_me,
-                pressure_elephant + node.value,
-                ticks_elephant,
-                max
+            self.explore(
+                valve,
+                minutes,
+                opened | (1 << valve),
+                pressure + rate * minutes,
+                best
             );
         }
     }
+}
 
-    // recursive case
-    for dst_me in to_visit.iter().take(to_visit.len()) {
-        let node_me = &graph.get(dst_me).unwrap();
-
-        let mut remaining = to_visit.clone();
-        remaining.remove(dst_me);
-
-        let minutes_to_dst_me = distances
-            .get(me)
-            .unwrap()
-            .get(dst_me)
-            .unwrap();
-        let new_minutes_me = (minutes_to_dst_me + 1).min(26 - minutes_me);
-
-        let mut new_ticks = ticks;
-        for i in minutes_me..(minutes_me + new_minutes_me) {
-            new_ticks[i] += pressure_me;
-        }
-
-        for dst_elephant in remaining.iter() {
-            if dst_me == dst_elephant {
-                continue;
-            }
-
-            let node_elephant = &graph.get(dst_elephant).unwrap();
-
-            let mut remaining = remaining.clone();
-            remaining.remove(dst_elephant);
+pub fn try_part1(input: &str) -> Result<usize, Error> {
+    let valves = Valves::parse(input)?;
 
-            let minutes_to_dst_elephant = distances
-                .get(elephant)
-                .unwrap()
-                .get(dst_elephant)
-                .unwrap();
+    Ok(valves
+        .best_per_set(30)
+        .into_iter()
+        .max()
+        .unwrap())
+}
 
-            let new_minutes_elephant = (minutes_to_dst_elephant + 1).min(26 - minutes_elephant);
+pub fn try_part2(input: &str) -> Result<usize, Error> {
+    let valves = Valves::parse(input)?;
+    let mut best = valves.best_per_set(26);
 
-            let mut new_ticks = new_ticks;
-            for j in minutes_elephant..(minutes_elephant + new_minutes_elephant) {
-                new_ticks[j] += pressure_elephant;
+    // widen each set's best to the best of any of its subsets, so that an
+    // agent's best can be looked up by the valves left to it
+    for valve in 0..valves.rates.len() {
+        for set in 0..best.len() {
+            if set & (1 << valve) != 0 {
+                best[set] = best[set].max(best[set ^ (1 << valve)]);
             }
-
-
-            max_flow_with_elephant(
-                graph,
-                distances,
-                dst_me,
-                dst_elephant,
-                remaining,
-                minutes_me + new_minutes_me,
-                minutes_elephant + new_minutes_elephant,
-                pressure_me + node_me.value,
-                pressure_elephant + node_elephant.value,
-                new_ticks,
-                max
-            )
         }
     }
-}
-
-pub fn part1(input: &str) -> usize {
-    let mut graph = HashMap::new();
-
-    input
-        .trim()
-        .lines()
-        .map(Node::from)
-        .for_each(|node_result| {
-            let (node, key) = node_result;
-
-            graph.insert(key, node);
-        });
-
-    let to_visit = graph
-        .iter()
-        .filter_map(|(key, node)| {
-            if node.value > 0 {
-                Some(*key)
-            } else {
-                None
-            }
-        })
-        .collect::<HashSet<_>>();
 
-    let distances = graph
-        .keys()
-        .map(|key| {
-            let key_distances = graph
-                .keys()
-                .into_iter()
-                .map(|other_key| (*other_key, bfs(&graph, key, other_key).unwrap()))
-                .collect::<HashMap<&str, usize>>();
-            (*key, key_distances)
-        })
-        .collect::<HashMap<&str, HashMap<&str, usize>>>();
+    // me and the elephant open disjoint sets of valves
+    let all = best.len() - 1;
 
-    println!("{:?}", to_visit);
-    println!("nodes with valves: {}", to_visit.len());
-
-    let mut max = 0;
-
-    max_flow(
-        &graph,
-        &distances,
-        "AA",
-        to_visit,
-        0,
-        0,
-        0,
-        &mut max
-    );
+    Ok((0..best.len())
+        .map(|mine| best[mine] + best[all & !mine])
+        .max()
+        .unwrap())
+}
 
-    max
+pub fn part1(input: &str) -> usize {
+    try_part1(input).unwrap()
 }
 
 pub fn part2(input: &str) -> usize {
-    let mut graph = HashMap::new();
-
-    input
-        .trim()
-        .lines()
-        .map(Node::from)
-        .for_each(|node_result| {
-            let (node, key) = node_result;
-
-            graph.insert(key, node);
-        });
-
-    let to_visit = graph
-        .iter()
-        .filter_map(|(key, node)| {
-            if node.value > 0 {
-                Some(*key)
-            } else {
-                None
-            }
-        })
-        .collect::<BTreeSet<_>>();
-
-    let distances = graph
-        .keys()
-        .map(|key| {
-            let key_distances = graph
-                .keys()
-                .into_iter()
-                .map(|other_key| (*other_key, bfs(&graph, key, other_key).unwrap()))
-                .collect::<HashMap<&str, usize>>();
-            (*key, key_distances)
-        })
-        .collect::<HashMap<&str, HashMap<&str, usize>>>();
-
-    println!("{:?}", to_visit);
-    println!("nodes with valves: {}", to_visit.len());
-
-    let mut max = 0;
-
-    max_flow_with_elephant(
-        &graph,
-        &distances,
-        "AA",
-        "AA",
-        to_visit,
-        0,
-        0,
-        0,
-        0,
-        [0; 26],
-        &mut max
-    );
-
-    max
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, try_part1};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB\n\
                               Valve BB has flow rate=13; tunnels lead to valves CC, AA\n\
@@ -467,5 +271,27 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 1707);
     }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            try_part1("Valve AA has flow rate=0; tunnels lead to valves BB\n\
+                       Valve BB has flow rate=x; tunnels lead to valves AA\n"),
+            Err(Error::Parse {
+                day: 16,
+                line: 2,
+                column: 24,
+                expected: "a flow rate".to_string()
+            })
+        );
+        assert!(try_part1("Valve BB has flow rate=1; tunnel leads to valve BB\n").is_err());
+
+        // CC can't be reached
+        let input = "Valve AA has flow rate=0; tunnel leads to valve BB\n\
+                     Valve BB has flow rate=5; tunnel leads to valve AA\n\
+                     Valve CC has flow rate=9; tunnel leads to valve AA\n";
+
+        assert_eq!(try_part1(input), Ok(5 * 28));
+    }
 }
 
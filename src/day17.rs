@@ -1,421 +1,296 @@
-#![allow(clippy::int_plus_one)]
-#![allow(clippy::needless_range_loop)]
-
-#[derive(Clone,Copy,PartialEq)]
-enum Shape {
-    Minus,
-    Plus,
-    MirrorL,
-    Pipe,
-    Square
-}
+use std::fmt;
 
-#[derive(Clone,Copy,PartialEq)]
-struct Rock {
-    shape: Shape,
-    x: i64,
-    y: i64
-}
+use crate::cycle::Detector;
+use crate::error::Error;
+use crate::grid::{Grid, Tile};
 
-impl Rock {
-    fn width(&self) -> usize {
-        match self.shape {
-            Shape::Minus => 4,
-            Shape::Plus => 3,
-            Shape::MirrorL => 3,
-            Shape::Pipe => 1,
-            Shape::Square => 2
-        }
-    }
+const DAY: u8 = 17;
 
-    fn height(&self) -> usize {
-        match self.shape {
-            Shape::Minus => 1,
-            Shape::Plus => 3,
-            Shape::MirrorL => 3,
-            Shape::Pipe => 4,
-            Shape::Square => 2
-        }
-    }
-}
+// how far down from the top of the tower the state looks. deeper rocks
+// hardly ever change how the next ones fall, and a column that never fills
+// would otherwise keep the state from repeating.
+const DEPTH: usize = 64;
 
-struct RockMaker {
-    prev: Option<Shape>
+// a rock's cells as (x, y) from its bottom left corner, with y growing upwards
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Shape {
+    cells: Vec<(usize, usize)>,
 }
 
-impl RockMaker {
-    fn new() -> Self {
-        Self { prev: None } 
+impl Shape {
+    pub fn new(cells: Vec<(usize, usize)>) -> Self {
+        let min_x = cells.iter().map(|cell| cell.0).min().unwrap_or(0);
+        let min_y = cells.iter().map(|cell| cell.1).min().unwrap_or(0);
+
+        let cells = cells
+            .into_iter()
+            .map(|(x, y)| (x - min_x, y - min_y))
+            .collect();
+
+        Self { cells }
     }
 
-    fn make(&mut self, x: i64, y: i64) -> Rock {
-        let shape = match self.prev {
-            None => Shape::Minus,
-            Some(Shape::Minus) => Shape::Plus,
-            Some(Shape::Plus) => Shape::MirrorL,
-            Some(Shape::MirrorL) => Shape::Pipe,
-            Some(Shape::Pipe) => Shape::Square,
-            Some(Shape::Square) => Shape::Minus
-        };
+    // a picture of the rock as in the puzzle, top row first
+    pub fn parse(picture: &str) -> Self {
+        let rows: Vec<&str> = picture.lines().collect();
+
+        let cells = rows
+            .iter()
+            .rev()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row
+                    .bytes()
+                    .enumerate()
+                    .filter(|&(_, b)| b == b'#')
+                    .map(move |(x, _)| (x, y))
+            })
+            .collect();
+
+        Self::new(cells)
+    }
 
-        self.prev = Some(shape);
+    pub fn width(&self) -> usize {
+        self.cells.iter().map(|cell| cell.0 + 1).max().unwrap_or(0)
+    }
 
-        Rock { shape, x, y }
+    pub fn height(&self) -> usize {
+        self.cells.iter().map(|cell| cell.1 + 1).max().unwrap_or(0)
     }
 }
 
-#[derive(Clone)]
-struct Cave {
-    rows: Vec<[bool; 7]>
+// the rocks from the puzzle, in the order they fall
+pub fn puzzle_shapes() -> Vec<Shape> {
+    ["####", ".#.\n###\n.#.", "..#\n..#\n###", "#\n#\n#\n#", "##\n##"]
+        .iter()
+        .map(|picture| Shape::parse(picture))
+        .collect()
 }
 
-impl Cave {
-    fn height(&self) -> usize {
-        self.rows.len()
-    }
+// where rocks fall: how wide the chamber is, how far from the left wall and
+// above the tower each rock appears, how many rocks fall and their shapes,
+// which take turns in order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chamber {
+    pub width: usize,
+    pub spawn: (usize, usize),
+    pub rocks: usize,
+    pub shapes: Vec<Shape>,
+}
 
-    fn collides_with_wall(&self, rock: &Rock) -> bool {
-        rock.x < 0 || rock.x + rock.width() as i64 - 1 > 6 || rock.y < 0
+impl Default for Chamber {
+    fn default() -> Self {
+        Self { width: 7, spawn: (2, 3), rocks: 2022, shapes: puzzle_shapes() }
     }
+}
 
-    fn collides(&self, rock: &Rock) -> bool {
-        // check for collisions with cave wall
-        if self.collides_with_wall(rock) {
-            return true;
-        }
+impl Chamber {
+    // height of the tower once all the rocks have fallen, simulating only
+    // until the tower starts repeating itself
+    pub fn tower_height(&self, input: &str) -> Result<usize, Error> {
+        assert!(!self.shapes.is_empty(), "no shapes");
+        assert!(
+            self.shapes.iter().all(|shape| self.spawn.0 + shape.width() <= self.width),
+            "shape doesn't fit in the chamber"
+        );
 
-        let (rock_x, rock_y) = (rock.x as usize, rock.y as usize);
-        let rows = &self.rows;
-        let height = self.height();
+        let mut simulation = Simulation::new(self, jets(input)?);
+        let mut detector = Detector::new();
 
-        // return false if new rock is higher than any existing rock
-        if rock_y >= height {
+        for _ in 0..self.rocks {
+            let height = simulation.cave.height() as i64;
 
-            return false;
-        }
+            if let Some(cycle) = detector.push(simulation.state(), height) {
+                return Ok(cycle.extrapolate(detector.values(), self.rocks) as usize);
+            }
 
-        match rock.shape {
-            Shape::Minus => {
-                for x in rock_x..(rock_x + rock.width()) {
-                    if rows[rock_y][x] {
-                        return true;
-                    }
-                }
-            },
-            Shape::Plus => {
-                // check rock's bottom row against grid
-                if rows[rock_y][rock_x + 1] {
-                    return true;
-                }
-
-                // check rock's middle row against grid
-                if height > rock_y + 1 {
-                    for x in rock_x..(rock_x + rock.width()) {
-                        if rows[rock_y + 1][x] {
-                            return true;
-                        }
-                    }
-                }
-
-                // check rock's top row against grid
-                if height > rock_y + 2 && rows[rock_y + 2][rock_x + 1] {
-                    return true;
-                }
-            },
-            Shape::MirrorL => {
-                // check rock's bottom row against grid
-                for x in rock_x..(rock_x + rock.width()) {
-                    if rows[rock_y][x] {
-                        return true;
-                    }
-                }
-
-                // check rock's middle row against grid
-                if height > rock_y + 1 && rows[rock_y + 1][rock_x + 2] {
-                    return true;
-                }
-
-                // check rock's top row against grid
-                if height  > rock_y + 2 && rows[rock_y + 2][rock_x + 2] {
-                    return true;
-                }
-            },
-            Shape::Pipe => {
-                // check each row against rock_x
-                for y in rock_y..height.min(rock_y + rock.height()) {
-                    if rows[y][rock_x] {
-                        return true;
-                    }
-                }
-            },
-            Shape::Square => {
-                // check rock's bottom row against grid
-                for x in rock_x..(rock_x + rock.width()) {
-                    if rows[rock_y][x] {
-                        return true;
-                    }
-                }
-
-                // check rock's middle row against grid
-                if height - 1 >= rock_y + 1 {
-                    for x in rock_x..(rock_x + rock.width()) {
-                        if rows[rock_y + 1][x] {
-                            return true;
-                        }
-                    }
-                }
-            },
+            simulation.drop_rock();
         }
 
-        let mut test_cave = self.clone();
-        test_cave.add(rock);
-
-        false
+        Ok(simulation.cave.height())
     }
+}
 
-    fn add(&mut self, rock: &Rock) {
-        let cave_height = self.height();
-        let rows = &mut self.rows;
-
-        let height_diff = rock.y + rock.height() as i64 - cave_height as i64;
-        for _ in 0..height_diff {
-            rows.push([false; 7]);
-        }
+// the pattern of jets, which must have at least one
+fn jets(input: &str) -> Result<&[u8], Error> {
+    let jets = input.trim();
 
-        let (rock_x, rock_y) = (rock.x as usize, rock.y as usize);
-        
-        match rock.shape {
-            Shape::Minus => {
-                rows[rock_y][rock_x..rock_x + rock.width()].fill(true);
-            },
-            Shape::Plus => {
-                rows[rock_y][rock_x + 1] = true;
-                rows[rock_y + 1][rock_x..rock_x + rock.width()].fill(true);
-                rows[rock_y + 2][rock_x + 1] = true;
-            },
-            Shape::MirrorL => {
-                rows[rock_y][rock_x..rock_x + rock.width()].fill(true);
-                rows[rock_y + 1][rock_x + 2] = true;
-                rows[rock_y + 2][rock_x + 2] = true;
-            },
-            Shape::Pipe => {
-                for y in rock_y..(rock_y + rock.height()) {
-                    rows[y][rock_x] = true;
-                }
-            },
-            Shape::Square => {
-                for y in rock_y..(rock_y + rock.height()) {
-                    rows[y][rock_x..rock_x + rock.width()].fill(true);
-                }
-            },
-        }
+    if let Some(i) = jets.find(|c: char| c != '<' && c != '>') {
+        return Err(Error::parse(DAY, input, &jets[i..], "`<` or `>`"));
     }
-}
 
-pub fn part1(input: &str) -> usize {
-    let jets_input = input.trim().as_bytes().iter();
-    let mut jets = jets_input.clone();
-    let mut rockmaker = RockMaker { prev: None };
+    if jets.is_empty() {
+        return Err(Error::parse(DAY, input, jets, "a jet pattern"));
+    }
 
-    let mut cave = Cave { rows: vec![] };
+    Ok(jets.as_bytes())
+}
 
+#[derive(Clone, Copy)]
+struct Rock<'a> {
+    shape: &'a Shape,
+    x: i64,
+    y: i64,
+}
 
-    for _ in 0..2022 {
-        let mut rock = rockmaker.make(2, cave.height() as i64 + 3);
+impl Rock<'_> {
+    fn cells(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.shape
+            .cells
+            .iter()
+            .map(|&(x, y)| (self.x + x as i64, self.y + y as i64))
+    }
+}
 
-        loop {
-            if jets.len() == 0 {
-                jets = jets_input.clone();
-            }
+#[derive(Clone)]
+struct Cave {
+    rows: Grid<bool>,
+}
 
-            let mut moved_rock = rock;
+impl Cave {
+    fn new(width: usize) -> Self {
+        Self { rows: Grid::new(width, 0, false) }
+    }
 
-            moved_rock.x = match jets.next().unwrap() {
-                b'<' => moved_rock.x - 1,
-                b'>' => moved_rock.x + 1,
-                _ => panic!()
-            };
+    fn height(&self) -> usize {
+        self.rows.height()
+    }
 
-            if cave.collides(&moved_rock) {
-                moved_rock = rock;
-            }
+    fn collides(&self, rock: &Rock) -> bool {
+        rock.cells().any(|(x, y)| {
+            // the walls and the floor, then the rocks that have come to rest
+            x < 0
+                || x >= self.rows.width() as i64
+                || y < 0
+                || self.rows.get((x, y)) == Some(&true)
+        })
+    }
 
-            moved_rock.y -= 1;
+    fn add(&mut self, rock: &Rock) {
+        let top = rock.y as usize + rock.shape.height();
 
-            // if rock can't drop any further, add rock to cave
-            if cave.collides(&moved_rock) {
-                moved_rock.y += 1;
-                cave.add(&moved_rock);
-                break;
-            }
+        while self.height() < top {
+            self.rows.push_row(false);
+        }
 
-            rock = moved_rock;
+        for (x, y) in rock.cells() {
+            self.rows[(x as usize, y as usize)] = true;
         }
     }
-
-    cave.height()
-}
-
-struct Groundhog {
-    rock: Rock,
-    start_i: usize,
-    start_height: usize,
-    end_i: Option<usize>,
-    end_height: Option<usize>
 }
 
-impl Groundhog {
-    fn delta_i(&self) -> Result<usize, ()> {
-        if let Some(end_i) = self.end_i {
-            Ok(end_i - self.start_i)
-        } else {
-            Err(())
+impl fmt::Display for Cave {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the tower grows upwards
+        for row in self.rows.rows().rev() {
+            writeln!(f, "|{}|", row.iter().map(Tile::to_char).collect::<String>())?;
         }
-    }
 
-    fn delta_height(&self) -> Result<usize, ()> {
-        if let Some(end_height) = self.end_height {
-            Ok(end_height - self.start_height)
-        } else {
-            Err(())
-        }
+        writeln!(f, "+{}+", "-".repeat(self.rows.width()))
     }
 }
 
-pub fn part2(input: &str) -> usize {
-    let jets_input = input.trim().as_bytes().iter();
-    let mut jets = jets_input.clone();
+// a tower of rocks being dropped one after another
+struct Simulation<'a> {
+    chamber: &'a Chamber,
+    jets: &'a [u8],
+    jet: usize,
+    rocks: usize,
+    cave: Cave,
+}
 
-    let mut rockmaker = RockMaker { prev: None };
-    let mut cave = Cave { rows: vec![] };
-    let mut groundhog: Option<Groundhog> = None;
+impl<'a> Simulation<'a> {
+    fn new(chamber: &'a Chamber, jets: &'a [u8]) -> Self {
+        Self { chamber, jets, jet: 0, rocks: 0, cave: Cave::new(chamber.width) }
+    }
 
-    let mut i = 0;
+    fn next_shape(&self) -> usize {
+        self.rocks % self.chamber.shapes.len()
+    }
 
-    while groundhog.is_none() || groundhog.as_ref().unwrap().end_i.is_none() {
-        let mut rock = rockmaker.make(2, cave.height() as i64 + 3);
+    fn drop_rock(&mut self) {
+        let mut rock = Rock {
+            shape: &self.chamber.shapes[self.next_shape()],
+            x: self.chamber.spawn.0 as i64,
+            y: (self.cave.height() + self.chamber.spawn.1) as i64,
+        };
 
         loop {
-            // check if we have run out of jets
-            if jets.len() == 0 {
-
-                if let Some(hog) = &mut groundhog {
-                    // if we have already found a groundhog, check if we can use
-                    // the current rock as an offset and store the amount of
-                    // iterations it took to get here and the difference in 
-                    // stack height
-                    if hog.rock.shape == rock.shape && hog.rock.x == rock.x {
-                        hog.rock = rock;
-                        hog.end_i = Some(i);
-                        hog.end_height = Some(cave.height());
-                        break;
-                    } else if hog.rock.shape == rock.shape {
-                        // something has gone wrong, the shape matches but
-                        // the x coordinate doesn't. never mind, take this
-                        // rock as groundhog instead
-                        groundhog = Some(Groundhog { 
-                            rock,
-                            start_i: i,
-                            start_height: cave.height(),
-                            end_i: None,
-                            end_height: None
-                        });
-                    }
-                } else {
-                    // if we hadn't yet picked a groundhog, use this one 
-                    groundhog = Some(Groundhog {
-                        rock,
-                        start_i: i,
-                        start_height: cave.height(),
-                        end_i: None,
-                        end_height: None
-                    });
-                }
-
-                // get a new iterator over our jets
-                jets = jets_input.clone();
-            }
-
             let mut moved_rock = rock;
 
-            moved_rock.x = match jets.next().unwrap() {
+            moved_rock.x = match self.jets[self.jet] {
                 b'<' => moved_rock.x - 1,
                 b'>' => moved_rock.x + 1,
                 _ => panic!()
             };
 
-            if cave.collides(&moved_rock) {
+            self.jet = (self.jet + 1) % self.jets.len();
+
+            if self.cave.collides(&moved_rock) {
                 moved_rock = rock;
             }
 
             moved_rock.y -= 1;
 
-            if cave.collides(&moved_rock) {
+            // if rock can't drop any further, add rock to cave
+            if self.cave.collides(&moved_rock) {
                 moved_rock.y += 1;
-                cave.add(&moved_rock);
+                self.cave.add(&moved_rock);
                 break;
             }
 
             rock = moved_rock;
         }
 
-        i += 1;
+        self.rocks += 1;
     }
 
-    let groundhog = groundhog.unwrap();
-
-    let count = (1000000000000usize - i) / groundhog.delta_i().unwrap();
-    let skipped_height = count * groundhog.delta_height().unwrap();
-
-    i += count * groundhog.delta_i().unwrap() - 1;
-
-    let mut groundhog = Some(groundhog);
-
-    while i < 1000000000000usize {
-        let mut rock = if let Some(hog) = groundhog {
-            hog.rock
-        } else {
-            rockmaker.make(2, cave.height() as i64 + 3)
-        };
-        groundhog = None;
-
-        loop {
-            if jets.len() == 0 {
-                jets = jets_input.clone();
-            }
-
-            let mut moved_rock = rock;
-
-            moved_rock.x = match jets.next().unwrap() {
-                b'<' => moved_rock.x - 1,
-                b'>' => moved_rock.x + 1,
-                _ => panic!()
-            };
-
-            if cave.collides(&moved_rock) {
-                moved_rock = rock;
-            }
+    // what decides how the next rocks fall: the next jet, the next shape, and
+    // how far down from the top each column is open, up to `DEPTH`
+    fn state(&self) -> (usize, usize, Vec<usize>) {
+        let rows = &self.cave.rows;
+        let height = rows.height();
+
+        let profile = (0..rows.width())
+            .map(|x| {
+                (0..height)
+                    .rev()
+                    .take(DEPTH)
+                    .position(|y| rows[(x, y)])
+                    .unwrap_or(height)
+                    .min(DEPTH)
+            })
+            .collect();
+
+        (self.jet, self.next_shape(), profile)
+    }
+}
 
-            moved_rock.y -= 1;
+// height of the tower after `rocks` rocks in the puzzle's chamber
+pub fn tower_height(input: &str, rocks: usize) -> Result<usize, Error> {
+    Chamber { rocks, ..Chamber::default() }.tower_height(input)
+}
 
-            if cave.collides(&moved_rock) {
-                moved_rock.y += 1;
-                cave.add(&moved_rock);
-                break;
-            }
+pub fn try_part1(input: &str) -> Result<usize, Error> {
+    tower_height(input, 2022)
+}
 
-            rock = moved_rock;
-        }
+pub fn try_part2(input: &str) -> Result<usize, Error> {
+    tower_height(input, 1_000_000_000_000)
+}
 
-        i += 1;
-    }
+pub fn part1(input: &str) -> usize {
+    try_part1(input).unwrap()
+}
 
-    cave.height() + skipped_height
+pub fn part2(input: &str) -> usize {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, tower_height, try_part1, Chamber, Shape, Simulation};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>\n";
 
@@ -428,5 +303,73 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 1514285714288);
     }
-}
 
+    #[test]
+    fn test_tower_height() {
+        let chamber = Chamber::default();
+        let mut simulation = Simulation::new(&chamber, TEST_INPUT.trim().as_bytes());
+        let mut heights = vec![0];
+
+        for _ in 0..3000 {
+            simulation.drop_rock();
+            heights.push(simulation.cave.height());
+        }
+
+        for rocks in (0..=3000).step_by(97) {
+            assert_eq!(tower_height(TEST_INPUT, rocks), Ok(heights[rocks]));
+        }
+    }
+
+    #[test]
+    fn test_chamber() {
+        // rocks that are all one cell, falling in a chamber one cell wide,
+        // just stack up
+        let pebbles = Chamber {
+            width: 1,
+            spawn: (0, 5),
+            rocks: 1_000_000,
+            shapes: vec![Shape::parse("#")],
+        };
+
+        assert_eq!(pebbles.tower_height(TEST_INPUT), Ok(1_000_000));
+
+        // the same goes for squares that fill the chamber
+        let squares = Chamber {
+            width: 2,
+            spawn: (0, 3),
+            shapes: vec![Shape::parse("##\n##")],
+            ..pebbles.clone()
+        };
+
+        assert_eq!(squares.tower_height(TEST_INPUT), Ok(2_000_000));
+
+        // pebbles blown against the left wall, leaving the right column empty
+        let leaning = Chamber {
+            width: 2,
+            spawn: (0, 3),
+            rocks: 1_000_000_000_000,
+            shapes: vec![Shape::parse("#")],
+        };
+
+        assert_eq!(leaning.tower_height("<<<<\n"), Ok(1_000_000_000_000));
+
+        let minus = Shape::parse("####");
+        assert_eq!((minus.width(), minus.height()), (4, 1));
+        assert_eq!(Shape::new(vec![(3, 5), (4, 5)]), Shape::parse("##\n"));
+        assert_eq!(Chamber::default().tower_height(TEST_INPUT), Ok(3068));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            try_part1(">><x<\n"),
+            Err(Error::Parse {
+                day: 17,
+                line: 1,
+                column: 4,
+                expected: "`<` or `>`".to_string()
+            })
+        );
+        assert!(try_part1("\n").is_err());
+    }
+}
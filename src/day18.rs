@@ -1,167 +1,190 @@
 use std::collections::VecDeque;
 
-#[derive(Debug,Clone,Copy,PartialEq,Eq)]
-struct Vertex {
-    x: i64,
-    y: i64,
-    z: i64,
+use crate::error::Error;
+
+const DAY: u8 = 18;
+
+// the most cubes the box around the droplet may hold, at a bit each
+const MAX_VOLUME: i64 = 1 << 30;
+
+type Cube = [i64; 3];
+
+fn adjacent(cube: Cube) -> [Cube; 6] {
+    let [x, y, z] = cube;
+
+    [
+        [x + 1, y, z],
+        [x - 1, y, z],
+        [x, y + 1, z],
+        [x, y - 1, z],
+        [x, y, z + 1],
+        [x, y, z - 1],
+    ]
 }
 
-#[derive(Debug,Clone,Copy)]
-struct Side {
-    vertices: [Vertex; 4],
+// a dense bitset of cubes within a box, with one empty layer all around the
+// cubes it was built from so that the outside is connected
+struct Voxels {
+    min: Cube,
+    size: [i64; 3],
+    bits: Vec<u64>,
 }
 
-impl PartialEq for Side {
-    fn eq(&self, other: &Self) -> bool {
-        self.vertices.iter().all(|vertex| other.vertices.contains(vertex))
+impl Voxels {
+    fn new(cubes: &[Cube]) -> Result<Self, Error> {
+        let mut min = [i64::MAX; 3];
+        let mut max = [i64::MIN; 3];
+
+        for cube in cubes {
+            for ((min, max), &c) in min.iter_mut().zip(&mut max).zip(cube) {
+                *min = (*min).min(c - 1);
+                *max = (*max).max(c + 1);
+            }
+        }
+
+        // without cubes, the box is empty
+        let size = [0, 1, 2].map(|i| max[i].saturating_sub(min[i]).saturating_add(1).max(0));
+
+        let len = size
+            .iter()
+            .try_fold(1, |len: i64, &size| len.checked_mul(size))
+            .filter(|&len| len <= MAX_VOLUME)
+            .ok_or_else(|| Error::no_solution(DAY, "the droplet is too large"))?;
+
+        let mut voxels = Self { min, size, bits: vec![0; (len as usize).div_ceil(64)] };
+
+        for &cube in cubes {
+            voxels.insert(cube);
+        }
+
+        Ok(voxels)
+    }
+
+    // an empty set with the same bounds
+    fn empty_like(other: &Self) -> Self {
+        Self {
+            min: other.min,
+            size: other.size,
+            bits: vec![0; other.bits.len()],
+        }
     }
-}
 
-impl Eq for Side {}
+    fn index(&self, cube: Cube) -> Option<usize> {
+        let mut index = 0;
 
-#[derive(Debug,Clone,Copy,PartialEq)]
-struct Cube {
-    x: i64,
-    y: i64,
-    z: i64,
-}
+        for ((c, min), size) in cube.into_iter().zip(self.min).zip(self.size) {
+            let offset = c - min;
+
+            if offset < 0 || offset >= size {
+                return None;
+            }
 
-impl Cube {
-    fn from(input: &str) -> Self {
-        let pos: Vec<i64> = input
-            .split(',')
-            .map(|num| num.parse::<i64>().unwrap() + 1)
-            .collect();
+            index = index * size + offset;
+        }
 
-        Self { x: pos[0], y: pos[1], z: pos[2] }
+        Some(index as usize)
     }
 
-    fn vertices(&self) -> [Vertex; 8] {
-        let (x, y, z) = (self.x, self.y, self.z);
-
-        [
-            Vertex { x, y, z },
-            Vertex { x: x + 1, y,  z },
-            Vertex { x: x + 1, y: y + 1,  z },
-            Vertex { x, y: y + 1,  z },
-            Vertex { x, y, z: z + 1 },
-            Vertex { x: x + 1, y,  z: z + 1 },
-            Vertex { x: x + 1, y: y + 1,  z: z + 1 },
-            Vertex { x, y: y + 1,  z: z + 1 }
-        ]
+    fn contains(&self, cube: Cube) -> bool {
+        self.index(cube)
+            .is_some_and(|i| self.bits[i / 64] & (1 << (i % 64)) != 0)
     }
 
-    fn sides(&self) -> [Side; 6] {
-        let v = self.vertices();
-
-        [
-            // Bottom
-            Side { vertices: [ v[0], v[1], v[2], v[3] ] },
-            // Top
-            Side { vertices: [ v[4], v[5], v[6], v[7] ] },
-            // Left
-            Side { vertices: [ v[0], v[1], v[5], v[4] ] },
-            // Right
-            Side { vertices: [ v[2], v[3], v[7], v[6] ] },
-            // Front
-            Side { vertices: [ v[1], v[2], v[6], v[5] ] },
-            // Rear
-            Side { vertices: [ v[0], v[4], v[7], v[3] ] },
-        ]
+    fn in_bounds(&self, cube: Cube) -> bool {
+        self.index(cube).is_some()
     }
-}
 
-fn bfs(cubes: &[Cube]) -> usize {
-    let root = Cube { x: 0, y: 0, z: 0 };
-    let mut visited = [[[false; 24]; 24]; 24];
+    // returns whether the cube was newly added
+    fn insert(&mut self, cube: Cube) -> bool {
+        let i = self.index(cube).unwrap();
+        let was_set = self.bits[i / 64] & (1 << (i % 64)) != 0;
+
+        self.bits[i / 64] |= 1 << (i % 64);
 
-    let mut queue: VecDeque<Cube> = VecDeque::new();
-    queue.push_back(root);
+        !was_set
+    }
+}
 
-    let mut side_count = 0;
+fn parse(input: &str) -> Result<Vec<Cube>, Error> {
+    input
+        .trim()
+        .lines()
+        .map(|line| {
+            let pos: Vec<&str> = line.split(',').collect();
 
-    while let Some(cube) = queue.pop_front() {
-        for side in &cube.sides() {
-            for other_cube in cubes {
-                if other_cube.sides().contains(side) {
-                    side_count += 1;
-                }
+            if pos.len() != 3 {
+                return Err(Error::parse(DAY, input, line, "a cube like `1,2,3`"));
             }
-        }
 
-        let mut in_front = cube;
-        in_front.x += 1;
+            let mut cube = [0; 3];
 
-        let mut behind = cube;
-        behind.x -= 1;
-
-        let mut left = cube;
-        left.y -= 1;
+            for (c, num) in cube.iter_mut().zip(pos) {
+                *c = num
+                    .parse::<i32>()
+                    .map_err(|_| Error::parse(DAY, input, num, "a coordinate"))?
+                    .into();
+            }
 
-        let mut right = cube;
-        right.y += 1;
+            Ok(cube)
+        })
+        .collect()
+}
 
-        let mut above = cube;
-        above.z += 1;
+// flood the box around the droplet and count the faces it touches
+fn bfs(droplet: &Voxels) -> usize {
+    let mut outside = Voxels::empty_like(droplet);
+    let mut queue = VecDeque::from([droplet.min]);
+    let mut faces = 0;
 
-        let mut below = cube;
-        below.z -= 1;
+    // no cubes, no box
+    if !droplet.in_bounds(droplet.min) {
+        return 0;
+    }
 
-        let adjacent_cubes = [in_front, behind, left, right, above, below];
+    outside.insert(droplet.min);
 
-        for next in adjacent_cubes {
-            if next.x >= 0 && next.y >= 0 && next.z >= 0
-               && next.x < 24 && next.y < 24 && next.z < 24
-               && !cubes.contains(&next)
-               && !visited[next.z as usize][next.y as usize][next.x as usize] {
-                   visited[next.z as usize][next.y as usize][next.x as usize] = true;
-                   queue.push_back(next);
-               }
+    while let Some(cube) = queue.pop_front() {
+        for next in adjacent(cube) {
+            if droplet.contains(next) {
+                faces += 1;
+            } else if droplet.in_bounds(next) && outside.insert(next) {
+                queue.push_back(next);
+            }
         }
     }
 
-    side_count
+    faces
 }
 
-pub fn part1(input: &str) -> usize {
-    let cubes: Vec<Cube> = input
-        .trim()
-        .split('\n')
-        .map(Cube::from)
-        .collect();
+pub fn try_part1(input: &str) -> Result<usize, Error> {
+    let cubes = parse(input)?;
+    let droplet = Voxels::new(&cubes)?;
 
-    cubes
+    Ok(cubes
         .iter()
-        .map(|cube| {
-             cube
-                .sides()
-                .iter()
-                .filter(|side| {
-                    cubes
-                        .iter()
-                        .all(|other_cube|{
-                            other_cube == cube
-                            || !other_cube.sides().contains(side)
-                        })
-                }).count()
-        })
-        .sum()
+        .flat_map(|&cube| adjacent(cube))
+        .filter(|&next| !droplet.contains(next))
+        .count())
 }
 
-pub fn part2(input: &str) -> usize {
-    let cubes: Vec<Cube> = input
-        .trim()
-        .split('\n')
-        .map(Cube::from)
-        .collect();
+pub fn try_part2(input: &str) -> Result<usize, Error> {
+    let cubes = parse(input)?;
+
+    Ok(bfs(&Voxels::new(&cubes)?))
+}
 
-    bfs(&cubes)
+pub fn part1(input: &str) -> usize {
+    try_part1(input).unwrap()
+}
+
+pub fn part2(input: &str) -> usize {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, try_part1, try_part2};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "2,2,2\n\
                               1,2,2\n\
@@ -177,16 +200,61 @@ mod tests {
                               2,1,5\n\
                               2,3,5\n";
 
-    #[ignore]
     #[test]
     fn test_part1() {
         assert_eq!(part1(TEST_INPUT), 64);
     }
-    
-    #[ignore]
+
     #[test]
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 58);
     }
-}
 
+    #[test]
+    fn test_hollow() {
+        // a 10x10x10 shell, far from the origin, with a hole in the middle
+        // of one side
+        let mut cubes = vec![];
+
+        for x in 0..10 {
+            for y in 0..10 {
+                for z in 0..10 {
+                    let shell = [x, y, z].iter().any(|&c| c == 0 || c == 9);
+
+                    if shell && (x, y, z) != (0, 4, 4) {
+                        cubes.push(format!("{},{},{}", x - 1000, y + 1000, z));
+                    }
+                }
+            }
+        }
+
+        let input = cubes.join("\n");
+
+        // the hole trades its two faces for the four around it, and lets
+        // water reach the inside
+        assert_eq!(part1(&input), 6 * 10 * 10 + 6 * 8 * 8 + 2);
+        assert_eq!(part2(&input), 6 * 10 * 10 + 6 * 8 * 8 + 2);
+
+        let sealed = format!("{}\n-1000,1004,4\n", input);
+
+        assert_eq!(part2(&sealed), 6 * 10 * 10);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            try_part1("2,2,2\n1,2\n"),
+            Err(Error::Parse {
+                day: 18,
+                line: 2,
+                column: 1,
+                expected: "a cube like `1,2,3`".to_string()
+            })
+        );
+        assert_eq!(try_part2(""), Ok(0));
+        assert_eq!(
+            try_part2("0,0,0\n100000,100000,100000\n"),
+            Err(Error::no_solution(18, "the droplet is too large"))
+        );
+    }
+}
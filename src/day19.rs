@@ -1,186 +1,243 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread;
 
+use crate::error::{end_of, Error};
+
+const DAY: u8 = 19;
+
+// ore, clay, obsidian, geode
+const GEODE: usize = 3;
+
 #[derive(Clone,Copy)]
 struct World {
-    costs: [[i64; 3]; 4],
-    robots: [i64; 4],
-    resources: [i64; 4],
-    time: usize,
-    queued: Option<usize>,
+    robots: [i64; 3],
+    resources: [i64; 3],
+    time: i64,
+    // geodes that robots built so far will have cracked by the end
+    geodes: i64,
 }
 
 impl World {
-    fn new(costs: [[i64; 3]; 4], time: usize) -> Self {
+    fn new(time: usize) -> Self {
         Self {
-            costs,
-            robots: [1, 0, 0, 0],
-            resources: [0; 4],
-            time,
-            queued: None
+            robots: [1, 0, 0],
+            resources: [0; 3],
+            time: time as i64,
+            geodes: 0,
         }
     }
 
-    fn mine_resources(&mut self) {
-        for (i, p) in self.robots.iter().enumerate() {
-            self.resources[i] += p;
-        }
-    }
+    // minutes until the robots have gathered enough to pay `cost`, or None
+    // if no robot gathers a needed resource
+    fn wait_for(&self, cost: &[i64; 3]) -> Option<i64> {
+        let mut wait = 0;
+
+        for ((&cost, &resource), &robots) in cost
+            .iter()
+            .zip(&self.resources)
+            .zip(&self.robots)
+        {
+            let missing = cost - resource;
+
+            if missing > 0 {
+                if robots == 0 {
+                    return None;
+                }
 
-    fn can_afford(&self, kind: usize) -> bool {
-        self.resources[0] >= self.costs[kind][0]
-        && self.resources[1] >= self.costs[kind][1]
-        && self.resources[2] >= self.costs[kind][2]
-    }
+                wait = wait.max((missing + robots - 1) / robots);
+            }
+        }
 
-    fn build(&mut self, kind: usize) {
-        self.resources[0] -= self.costs[kind][0];
-        self.resources[1] -= self.costs[kind][1];
-        self.resources[2] -= self.costs[kind][2];
-        self.queued = Some(kind);
+        Some(wait)
     }
 
-    fn tick(&mut self, max: &mut [i64]) -> i64 {
+    // the world after waiting for and building a robot of `kind`
+    fn build(&self, blueprint: &Blueprint, kind: usize) -> Option<Self> {
+        let cost = &blueprint.costs[kind];
+        let minutes = self.wait_for(cost)? + 1;
 
-        // base case
-        if self.time == 0 {
-            return self.resources[3];
+        // a robot finished in the last minute cracks nothing
+        if minutes >= self.time {
+            return None;
         }
 
-        self.mine_resources();
-        self.time -= 1;
+        let mut world = *self;
+        world.time -= minutes;
 
-        // memoization
-        if max[self.time] > self.resources[3] {
-            return 0;
+        for ((resource, &robots), &cost) in world.resources
+            .iter_mut()
+            .zip(&world.robots)
+            .zip(cost)
+        {
+            *resource += robots * minutes - cost;
         }
-        max[self.time] = self.resources[3];
-        
-        if let Some(i) = self.queued {
-            self.robots[i] += 1;
-            self.queued = None;
+
+        if kind == GEODE {
+            world.geodes += world.time;
+        } else {
+            world.robots[kind] += 1;
         }
 
-        if self.time > 1 {
-            if self.can_afford(3) {
-                self.build(3);
-            } else if self.time > 3 {
-                let mut geodes_from_worlds = [0, 0, 0, 0];
-
-                let geodes_iter = geodes_from_worlds
-                    .iter_mut()
-                    .enumerate()
-                    .take(3);
-
-                for (i, geodes) in geodes_iter {
-                    if i == 0 && self.costs[i][0] > self.time as i64
-                       || i == 1 && self.time <= 5 {
-                        continue;
-                    }
+        Some(world)
+    }
 
-                    if !self.can_afford(i) {
-                        continue;
-                    }
+    // geodes if a geode robot could be built every remaining minute
+    fn upper_bound(&self) -> i64 {
+        self.geodes + self.time * (self.time - 1) / 2
+    }
 
-                    let mut new_world = self.to_owned();
-                    new_world.build(i);
-                    
-                    *geodes = new_world.tick(max);
-                }
+    fn search(&self, blueprint: &Blueprint, best: &mut i64) {
+        *best = (*best).max(self.geodes);
 
-                geodes_from_worlds[3] = self.tick(max);
+        if self.upper_bound() <= *best {
+            return;
+        }
 
-                return *geodes_from_worlds.iter().max().unwrap();
+        // geode robots first, so good bounds are found early
+        for kind in (0..4).rev() {
+            // only one robot can be built per minute, so more robots than
+            // the largest cost of a resource are of no use
+            if kind != GEODE && self.robots[kind] >= blueprint.max_spend[kind] {
+                continue;
             }
-        }
 
-        self.tick(max)
+            if let Some(world) = self.build(blueprint, kind) {
+                world.search(blueprint, best);
+            }
+        }
     }
 }
 
 struct Blueprint {
     id: usize,
     costs: [[i64; 3]; 4],
+    max_spend: [i64; 3],
 }
 
 impl Blueprint {
-    fn from(input: &str) -> Self {
-        let parts: Vec<_> = input.split([' ', ':']).collect();
-        let id = parts[1].parse().unwrap();
+    // `line` is a slice of `input`
+    fn parse(input: &str, line: &str) -> Result<Self, Error> {
+        let parts: Vec<_> = line.split([' ', ':']).collect();
+
+        if parts[0] != "Blueprint" {
+            return Err(Error::parse(DAY, input, line, "a blueprint"));
+        }
+
+        let number = |i: usize| {
+            let part = parts.get(i).copied().unwrap_or(end_of(line));
+
+            part
+                .parse::<u32>()
+                .map_err(|_| Error::parse(DAY, input, part, "a number"))
+        };
+
+        let id = number(1)? as usize;
 
         let costs = [
-            [parts[7].parse().unwrap(), 0, 0],
-            [parts[13].parse().unwrap(), 0, 0],
-            [parts[19].parse().unwrap(), parts[22].parse().unwrap(), 0],
-            [parts[28].parse().unwrap(), 0, parts[31].parse().unwrap()],
+            [number(7)?.into(), 0, 0],
+            [number(13)?.into(), 0, 0],
+            [number(19)?.into(), number(22)?.into(), 0],
+            [number(28)?.into(), 0, number(31)?.into()],
         ];
 
-        Self { id, costs }
+        let mut max_spend = [0; 3];
+
+        for cost in &costs {
+            for (max, &amount) in max_spend.iter_mut().zip(cost) {
+                *max = amount.max(*max);
+            }
+        }
+
+        Ok(Self { id, costs, max_spend })
     }
 
     fn simulate(&self, time: usize) -> i64 {
-        let mut world = World::new(self.costs, time);
-        let mut max = vec![0i64; time];
+        let mut best = 0;
+
+        World::new(time).search(self, &mut best);
 
-        world.tick(&mut max)
+        best
     }
 }
 
-pub fn part1(input: &str) -> usize {
-    let blueprints: Vec<_> = input
-        .lines()
-        .map(Blueprint::from)
-        .collect();
+// the most geodes for each blueprint, simulated on one thread per core
+fn simulate_all(blueprints: &[Blueprint], time: usize) -> Vec<i64> {
+    let workers = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(blueprints.len());
 
-    let mut handles = vec![];
+    let next = AtomicUsize::new(0);
+    let mut geodes = vec![0; blueprints.len()];
 
-    for blueprint in blueprints {
-        let handle = thread::spawn(move || {
-            let geodes = blueprint.simulate(24) as usize;
-            blueprint.id * geodes
-        });
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = vec![];
 
-        handles.push(handle);
-    }
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
 
-    let mut quality_levels = 0;
+                        match blueprints.get(i) {
+                            Some(blueprint) => results.push((i, blueprint.simulate(time))),
+                            None => return results,
+                        }
+                    }
+                })
+            })
+            .collect();
 
-    for handle in handles {
-        quality_levels += handle.join().unwrap();
-    }
+        for handle in handles {
+            for (i, result) in handle.join().unwrap() {
+                geodes[i] = result;
+            }
+        }
+    });
 
-    quality_levels
+    geodes
 }
 
-pub fn part2(input: &str) -> usize {
-    let mut blueprints: Vec<_> = input
+fn parse(input: &str) -> Result<Vec<Blueprint>, Error> {
+    input
         .lines()
-        .map(Blueprint::from)
-        .collect();
+        .filter(|line| !line.is_empty())
+        .map(|line| Blueprint::parse(input, line))
+        .collect()
+}
 
-    blueprints.truncate(3);
+pub fn try_part1(input: &str) -> Result<usize, Error> {
+    let blueprints = parse(input)?;
 
-    let mut handles = vec![];
+    Ok(simulate_all(&blueprints, 24)
+        .into_iter()
+        .zip(&blueprints)
+        .map(|(geodes, blueprint)| blueprint.id * geodes as usize)
+        .sum())
+}
 
-    for blueprint in blueprints {
-        let handle = thread::spawn(move || {
-            blueprint.simulate(32) as usize
-        });
+pub fn try_part2(input: &str) -> Result<usize, Error> {
+    let mut blueprints = parse(input)?;
 
-        handles.push(handle);
-    }
+    blueprints.truncate(3);
 
-    let mut geode_product = 1;
+    Ok(simulate_all(&blueprints, 32)
+        .into_iter()
+        .map(|geodes| geodes as usize)
+        .product())
+}
 
-    for handle in handles {
-        geode_product *= handle.join().unwrap();
-    }
+pub fn part1(input: &str) -> usize {
+    try_part1(input).unwrap()
+}
 
-    geode_product
+pub fn part2(input: &str) -> usize {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, try_part1};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "Blueprint 1: Each ore robot costs 4 ore. Each \
                               clay robot costs 2 ore. Each obsidian robot \
@@ -191,16 +248,28 @@ mod tests {
                               costs 3 ore and 8 clay. Each geode robot costs \
                               3 ore and 12 obsidian.\n";
 
-    #[ignore]
     #[test]
     fn test_part1() {
         assert_eq!(part1(TEST_INPUT), 33);
     }
     
-    #[ignore]
     #[test]
     fn test_part2() {
-        assert_eq!(part2(TEST_INPUT), 56);
+        assert_eq!(part2(TEST_INPUT), 56 * 62);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            try_part1("Blueprint 1: Each ore robot costs four ore.\n"),
+            Err(Error::Parse {
+                day: 19,
+                line: 1,
+                column: 35,
+                expected: "a number".to_string()
+            })
+        );
+        assert!(try_part1("Blueprint 1: Each ore robot costs 4 ore.\n").is_err());
     }
 }
 
@@ -1,97 +1,242 @@
-//use std::collections::VecDeque;
+use crate::error::Error;
 
-#[derive(Debug,Clone,Copy,Eq,PartialEq)]
-struct Coordinate {
-    val: i64,
-    pos: usize,
+const DAY: u8 = 20;
+
+const NIL: usize = usize::MAX;
+
+// an implicit treap: a randomised binary tree ordered by position, in which
+// each node is one of the numbers by its index in the input. parent links let
+// us find a number's current position without searching for it.
+struct Treap {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    priority: Vec<u64>,
+    root: usize,
 }
 
-pub fn part1(input: &str) -> i64 {
-    let coordinates: Vec<Coordinate> = input
-        .lines()
-        .enumerate()
-        .map(|(pos, coordinate)| Coordinate { 
-            pos,
-            val: coordinate.parse().unwrap()
-        })
-        .collect();
+impl Treap {
+    // the numbers 0..n, in order
+    fn new(n: usize) -> Self {
+        // xorshift, so that the tree stays balanced
+        let mut seed = 0x2545f4914f6cdd1d_u64;
+        let priority = (0..n)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                seed
+            })
+            .collect();
 
-    let mut new_coordinates = coordinates.clone();
+        let mut treap = Self {
+            left: vec![NIL; n],
+            right: vec![NIL; n],
+            parent: vec![NIL; n],
+            size: vec![1; n],
+            priority,
+            root: NIL,
+        };
 
-    for coordinate in coordinates.iter() {
-        let pos = new_coordinates
-            .iter()
-            .position(|new_coord| new_coord == coordinate)
-            .unwrap();
+        for node in 0..n {
+            treap.root = treap.merge(treap.root, node);
+        }
 
-        new_coordinates.remove(pos);
+        treap
+    }
 
-        let mut new_pos = (pos as i64 + coordinate.val)
-            .rem_euclid(new_coordinates.len() as i64) as usize;
+    fn len(&self) -> usize {
+        self.size_of(self.root)
+    }
+
+    fn size_of(&self, node: usize) -> usize {
+        if node == NIL { 0 } else { self.size[node] }
+    }
 
-        if new_pos == 0 {
-            new_pos = new_coordinates.len();
+    fn update(&mut self, node: usize) {
+        let (left, right) = (self.left[node], self.right[node]);
+
+        self.size[node] = 1 + self.size_of(left) + self.size_of(right);
+
+        for child in [left, right] {
+            if child != NIL {
+                self.parent[child] = node;
+            }
+        }
+    }
+
+    fn merge(&mut self, a: usize, b: usize) -> usize {
+        let root = if a == NIL {
+            b
+        } else if b == NIL {
+            a
+        } else if self.priority[a] > self.priority[b] {
+            self.right[a] = self.merge(self.right[a], b);
+            self.update(a);
+            a
+        } else {
+            self.left[b] = self.merge(a, self.left[b]);
+            self.update(b);
+            b
+        };
+
+        if root != NIL {
+            self.parent[root] = NIL;
         }
 
-        new_coordinates.insert(new_pos, *coordinate);
+        root
     }
 
-    let zero_pos = new_coordinates
-        .iter()
-        .position(|coord| coord.val == 0)
-        .unwrap();
+    // the first `k` nodes of `node`'s subtree, and the rest
+    fn split(&mut self, node: usize, k: usize) -> (usize, usize) {
+        if node == NIL {
+            return (NIL, NIL);
+        }
+
+        let left_size = self.size_of(self.left[node]);
+
+        let (a, b) = if k <= left_size {
+            let (a, b) = self.split(self.left[node], k);
+            self.left[node] = b;
+            self.update(node);
+            (a, node)
+        } else {
+            let (a, b) = self.split(self.right[node], k - left_size - 1);
+            self.right[node] = a;
+            self.update(node);
+            (node, b)
+        };
+
+        for root in [a, b] {
+            if root != NIL {
+                self.parent[root] = NIL;
+            }
+        }
+
+        (a, b)
+    }
+
+    fn position(&self, mut node: usize) -> usize {
+        let mut pos = self.size_of(self.left[node]);
+
+        while self.parent[node] != NIL {
+            let parent = self.parent[node];
+
+            if self.right[parent] == node {
+                pos += self.size_of(self.left[parent]) + 1;
+            }
+
+            node = parent;
+        }
+
+        pos
+    }
+
+    fn nth(&self, mut k: usize) -> usize {
+        let mut node = self.root;
+
+        loop {
+            let left_size = self.size_of(self.left[node]);
+
+            if k < left_size {
+                node = self.left[node];
+            } else if k == left_size {
+                return node;
+            } else {
+                k -= left_size + 1;
+                node = self.right[node];
+            }
+        }
+    }
+
+    // takes `node` out and returns where it was
+    fn remove(&mut self, node: usize) -> usize {
+        let pos = self.position(node);
 
-    new_coordinates[(zero_pos + 1000).rem_euclid(new_coordinates.len())].val
-    + new_coordinates[(zero_pos + 2000).rem_euclid(new_coordinates.len())].val
-    + new_coordinates[(zero_pos + 3000).rem_euclid(new_coordinates.len())].val
+        let (a, b) = self.split(self.root, pos);
+        let (_, c) = self.split(b, 1);
+        self.root = self.merge(a, c);
+
+        pos
+    }
+
+    fn insert(&mut self, node: usize, pos: usize) {
+        let (a, b) = self.split(self.root, pos);
+        let a = self.merge(a, node);
+        self.root = self.merge(a, b);
+    }
 }
 
-pub fn part2(input: &str) -> i64 {
-    let coordinates: Vec<Coordinate> = input
+// mix `rounds` times, after multiplying every number by `key`, and sum the
+// grove coordinates
+pub fn decrypt(input: &str, rounds: usize, key: i64) -> Result<i64, Error> {
+    let numbers: Vec<i64> = input
         .lines()
-        .enumerate()
-        .map(|(pos, coordinate)| Coordinate { 
-            pos,
-            val: coordinate.parse::<i64>().unwrap() * 811589153
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            line
+                .parse::<i32>()
+                .map_err(|_| Error::parse(DAY, input, line, "a number"))
+                .and_then(|val| {
+                    i64::from(val)
+                        .checked_mul(key)
+                        .ok_or_else(|| Error::no_solution(DAY, "the key is too large"))
+                })
         })
-        .collect();
+        .collect::<Result<_, _>>()?;
 
-    let mut new_coordinates = coordinates.clone();
+    let zero = numbers
+        .iter()
+        .position(|&val| val == 0)
+        .ok_or_else(|| Error::no_solution(DAY, "there is no 0"))?;
 
-    for _ in 0..10 {
-        for coordinate in coordinates.iter() {
-            let pos = new_coordinates
-                .iter()
-                .position(|new_coord| new_coord == coordinate)
-                .unwrap();
+    let mut treap = Treap::new(numbers.len());
 
-            new_coordinates.remove(pos);
+    for _ in 0..rounds {
+        for (node, val) in numbers.iter().enumerate() {
+            let pos = treap.remove(node);
 
-            let mut new_pos = (pos as i64 + coordinate.val)
-                .rem_euclid(new_coordinates.len() as i64) as usize;
+            // a lone number has nowhere else to go
+            let mut new_pos = (pos as i64 + val)
+                .rem_euclid(treap.len().max(1) as i64) as usize;
 
             if new_pos == 0 {
-                new_pos = new_coordinates.len();
+                new_pos = treap.len();
             }
 
-            new_coordinates.insert(new_pos, *coordinate);
+            treap.insert(node, new_pos);
         }
     }
 
-    let zero_pos = new_coordinates
+    let zero_pos = treap.position(zero);
+
+    Ok([1000, 2000, 3000]
         .iter()
-        .position(|coord| coord.val == 0)
-        .unwrap();
+        .map(|offset| numbers[treap.nth((zero_pos + offset) % numbers.len())])
+        .sum())
+}
 
-    new_coordinates[(zero_pos + 1000).rem_euclid(new_coordinates.len())].val
-    + new_coordinates[(zero_pos + 2000).rem_euclid(new_coordinates.len())].val
-    + new_coordinates[(zero_pos + 3000).rem_euclid(new_coordinates.len())].val
+pub fn try_part1(input: &str) -> Result<i64, Error> {
+    decrypt(input, 1, 1)
 }
 
+pub fn try_part2(input: &str) -> Result<i64, Error> {
+    decrypt(input, 10, 811589153)
+}
+
+pub fn part1(input: &str) -> i64 {
+    try_part1(input).unwrap()
+}
+
+pub fn part2(input: &str) -> i64 {
+    try_part2(input).unwrap()
+}
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{decrypt, part1, part2, try_part1, Treap};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "1\n\
                               2\n\
@@ -105,10 +250,80 @@ mod tests {
     fn test_part1() {
         assert_eq!(part1(TEST_INPUT), 3);
     }
-    
+
     #[test]
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 1623178306);
     }
-}
 
+    #[test]
+    fn test_treap() {
+        let mut treap = Treap::new(1000);
+        let mut list: Vec<usize> = (0..1000).collect();
+
+        for node in (0..1000).step_by(7) {
+            let pos = treap.remove(node);
+            assert_eq!(list.remove(pos), node);
+
+            let new_pos = (pos * 31) % list.len();
+            treap.insert(node, new_pos);
+            list.insert(new_pos, node);
+        }
+
+        assert!((0..1000).all(|k| treap.nth(k) == list[k]));
+        assert!((0..1000).all(|node| list[treap.position(node)] == node));
+    }
+
+    #[test]
+    fn test_decrypt() {
+        let numbers: Vec<i64> = (0..2000)
+            .map(|i: i64| (i * 7919) % 2003 - 1000)
+            .collect();
+
+        let input: String = numbers
+            .iter()
+            .map(|val| format!("{}\n", val))
+            .collect();
+
+        // mix the slow and obvious way
+        let key = 811589153;
+        let mut mixed: Vec<(usize, i64)> = numbers
+            .iter()
+            .map(|val| val * key)
+            .enumerate()
+            .collect();
+
+        for _ in 0..3 {
+            for i in 0..numbers.len() {
+                let pos = mixed.iter().position(|&(j, _)| i == j).unwrap();
+                let (_, val) = mixed.remove(pos);
+                let new_pos = (pos as i64 + val).rem_euclid(mixed.len() as i64);
+
+                mixed.insert(new_pos as usize, (i, val));
+            }
+        }
+
+        let zero = mixed.iter().position(|&(_, val)| val == 0).unwrap();
+        let expected: i64 = [1000, 2000, 3000]
+            .iter()
+            .map(|offset| mixed[(zero + offset) % mixed.len()].1)
+            .sum();
+
+        assert_eq!(decrypt(&input, 3, key), Ok(expected));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            try_part1("1\n2\nthree\n0\n"),
+            Err(Error::Parse {
+                day: 20,
+                line: 3,
+                column: 1,
+                expected: "a number".to_string()
+            })
+        );
+        assert_eq!(try_part1("1\n2\n"), Err(Error::no_solution(20, "there is no 0")));
+        assert_eq!(try_part1("0\n"), Ok(0));
+    }
+}
@@ -1,198 +1,207 @@
 use std::collections::HashMap;
 
-#[derive(Clone)]
-enum Operator {
-    Addition,
-    Subtraction,
-    Multiplication,
-    Division,
+use crate::error::Error;
+use crate::expression::{Equation, Expr, Operator, Roots};
+use crate::params::{self, value};
+
+const DAY: u8 = 21;
+
+// which monkey compares the two sides in part 2, and which monkey is us
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Params {
+    pub root: String,
+    pub variable: String,
 }
 
-#[derive(Clone)]
-struct Operation {
-    operator: Operator,
-    operand1: String,
-    operand2: String,
+impl Default for Params {
+    fn default() -> Self {
+        Self { root: "root".to_string(), variable: "humn".to_string() }
+    }
+}
+
+impl params::Params for Params {
+    fn example() -> Self {
+        Self::default()
+    }
+
+    fn set(&mut self, name: &str, val: &str) -> Result<(), String> {
+        match name {
+            "root" => self.root = value(val, "a monkey")?,
+            "variable" => self.variable = value(val, "a monkey")?,
+            _ => return Err("`root` or `variable`".to_string()),
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Clone)]
-enum Expression {
+enum Job {
     Number(i64),
-    Operation(Operation),
+    Operation(Operator, String, String),
 }
 
 #[derive(Clone)]
 struct Monkey {
     name: String,
-    val: Expression,
+    job: Job,
 }
 
 impl Monkey {
-    fn from(input: &str) -> Self {
-        let parts: Vec<&str> = input.split([' ', ':']).collect();
-
-        let name = parts[0].to_owned();
-        
-        let val = if parts.len() == 5 {
-            let operator = match parts[3].chars().next().unwrap() {
-                '+' => Operator::Addition,
-                '-' => Operator::Subtraction,
-                '*' => Operator::Multiplication,
-                '/' => Operator::Division,
-                _ => panic!()
-            };
-
-            Expression::Operation(
-                Operation {
-                    operator,
-                    operand1: parts[2].to_owned(),
-                    operand2: parts[4].to_owned(),
-                }
-            )
-        } else {
-            Expression::Number(parts[2].parse().unwrap())
+    // `line` is a slice of `input`
+    fn parse(input: &str, line: &str) -> Result<Self, Error> {
+        let (name, job) = line
+            .split_once(": ")
+            .ok_or_else(|| Error::parse(DAY, input, line, "`name: job`"))?;
+
+        let parts: Vec<&str> = job.split(' ').collect();
+
+        let job = match parts[..] {
+            [operand1, operator, operand2] => {
+                let operator = Operator::from_symbol(operator).ok_or_else(|| {
+                    Error::parse(DAY, input, operator, "`+`, `-`, `*` or `/`")
+                })?;
+
+                Job::Operation(operator, operand1.to_owned(), operand2.to_owned())
+            },
+            [number] => {
+                let number = number
+                    .parse()
+                    .map_err(|_| Error::parse(DAY, input, number, "a number"))?;
+
+                Job::Number(number)
+            },
+            _ => return Err(Error::parse(DAY, input, job, "a number or operation")),
         };
 
-        Self { name, val }
+        Ok(Self { name: name.to_owned(), job })
     }
+}
 
-    fn val(&self, table: &HashMap<String, Monkey>) -> Result<i64, ()> {
-        match &self.val {
-            Expression::Number(n) => Ok(*n),
-            Expression::Operation(o) => {
-                let a = table.get(&o.operand1).ok_or(())?.val(table)?;
-                let b = table.get(&o.operand2).ok_or(())?.val(table)?;
-
-                let result = match o.operator {
-                    Operator::Addition => a + b,
-                    Operator::Subtraction => a - b,
-                    Operator::Multiplication => a * b,
-                    Operator::Division => a / b,
-                };
-
-                Ok(result)
-            },
+struct Table {
+    monkeys: HashMap<String, Monkey>,
+}
+
+impl Table {
+    fn parse(input: &str) -> Result<Self, Error> {
+        let mut monkeys = HashMap::new();
+
+        for line in input.lines() {
+            let monkey = Monkey::parse(input, line)?;
+            monkeys.insert(monkey.name.clone(), monkey);
         }
+
+        Ok(Self { monkeys })
+    }
+
+    fn lookup(&self, name: &str) -> Result<&Monkey, Error> {
+        self.monkeys
+            .get(name)
+            .ok_or_else(|| Error::no_solution(DAY, &format!("unknown monkey `{}`", name)))
+    }
+
+    // the expression monkey `name` yells, with `variable` left unknown
+    fn expression(&self, name: &str, variable: Option<&str>) -> Result<Expr, Error> {
+        self.expression_within(name, variable, self.monkeys.len())
     }
 
-    #[allow(clippy::unnecessary_unwrap)]
-    fn solve_for(
+    // a monkey can't wait on more monkeys than there are
+    fn expression_within(
         &self,
-        table: &HashMap<String, Monkey>,
-        variable: &str,
-        val: i64
-    ) -> Result<i64, ()> {
-
-        match &self.val {
-            Expression::Number(_) => Err(()),
-            Expression::Operation(o) => {
-                let (left, right) = (
-                    table.get(&o.operand1),
-                    table.get(&o.operand2)
-                );
-
-                if left.is_some() && left.unwrap().val(table).is_ok() {
-
-                    let left = left.unwrap().val(table).unwrap();
-
-                    let val = match o.operator {
-                        Operator::Addition => val - left,
-                        Operator::Subtraction => left - val,
-                        Operator::Multiplication => val / left,
-                        Operator::Division => left / val,
-                    };
-
-                    if o.operand2 == variable {
-                        return Ok(val);
-                    }
-
-                    table
-                        .get(&o.operand2)
-                        .unwrap()
-                        .solve_for(table, variable, val)
-
-                } else if right.is_some() && right.unwrap().val(table).is_ok() {
-
-                    let right = right.unwrap().val(table).unwrap();
-
-                    let val = match o.operator {
-                        Operator::Addition => val - right,
-                        Operator::Subtraction => val + right,
-                        Operator::Multiplication => val / right,
-                        Operator::Division => val * right,
-                    };
-
-                    if o.operand1 == variable {
-                        return Ok(val);
-                    }
-
-                    table
-                        .get(&o.operand1)
-                        .unwrap()
-                        .solve_for(table, variable, val)
-
-                } else {
-                    Err(())
-                }
-            }
+        name: &str,
+        variable: Option<&str>,
+        depth: usize
+    ) -> Result<Expr, Error> {
+        if variable == Some(name) {
+            return Ok(Expr::Variable(name.to_string()));
+        }
+
+        let depth = depth
+            .checked_sub(1)
+            .ok_or_else(|| Error::no_solution(DAY, "monkeys wait on each other"))?;
+
+        match &self.lookup(name)?.job {
+            Job::Number(n) => Ok(Expr::Number(*n)),
+            Job::Operation(operator, a, b) => Ok(Expr::binary(
+                *operator,
+                self.expression_within(a, variable, depth)?,
+                self.expression_within(b, variable, depth)?,
+            )),
+        }
+    }
+
+    // `params.root`'s operands, which must be equal
+    fn equation(&self, params: &Params) -> Result<Equation, Error> {
+        let variable = Some(params.variable.as_str());
+
+        match &self.lookup(&params.root)?.job {
+            Job::Operation(_, a, b) => Ok(Equation {
+                left: self.expression(a, variable)?,
+                right: self.expression(b, variable)?,
+            }),
+            Job::Number(_) => {
+                Err(Error::no_solution(DAY, "root does not compare two monkeys"))
+            },
         }
     }
 }
 
-pub fn part1(input: &str) -> i64 {
-    let mut table: HashMap<String, Monkey> = HashMap::new();
+// the equation part 2 solves, e.g. `((4 + (2 * (humn - 3))) / 4) = 150`
+pub fn equation(input: &str, params: &Params) -> Result<Equation, Error> {
+    Table::parse(input)?.equation(params)
+}
+
+pub fn try_part1_with(input: &str, params: &Params) -> Result<i64, Error> {
+    let table = Table::parse(input)?;
 
-    input
-        .lines()
-        .map(Monkey::from)
-        .for_each(|monkey| { table.insert(monkey.name.clone(), monkey); } );
+    let value = table
+        .expression(&params.root, None)?
+        .eval()
+        .map_err(|reason| Error::no_solution(DAY, &reason))?;
 
-    table
-        .get("root")
-        .unwrap()
-        .val(&table)
-        .unwrap()
+    value
+        .to_integer()
+        .and_then(|n| i64::try_from(n).ok())
+        .ok_or_else(|| Error::no_solution(DAY, &format!("root yells {}", value)))
 }
 
-pub fn part2(input: &str) -> i64 {
-    let mut table: HashMap<String, Monkey> = HashMap::new();
-
-    input
-        .lines()
-        .map(Monkey::from)
-        .for_each(|monkey| { table.insert(monkey.name.clone(), monkey); } );
-
-    table.remove("humn");
-
-    let root_monkey = table.get("root").unwrap();
-
-    let (left, right) = match &root_monkey.val {
-        Expression::Operation(o) => {
-            (table.get(&o.operand1).unwrap(), table.get(&o.operand2).unwrap())
-        },
-        _ => panic!(),
-    };
-
-    if let Ok(a) = left.val(&table) {
-        table
-            .get(&right.name)
-            .unwrap()
-            .solve_for(&table, "humn", a)
-            .unwrap()
-    } else if let Ok(b) = right.val(&table) {
-        table
-            .get(&left.name)
-            .unwrap()
-            .solve_for(&table, "humn", b)
-            .unwrap()
-    } else {
-        panic!()
+pub fn try_part2_with(input: &str, params: &Params) -> Result<i64, Error> {
+    let roots = equation(input, params)?
+        .solve(&params.variable)
+        .map_err(|reason| Error::no_solution(DAY, &reason))?;
+
+    match roots {
+        Roots::One(value) => value
+            .to_integer()
+            .and_then(|n| i64::try_from(n).ok())
+            .ok_or_else(|| {
+                Error::no_solution(DAY, &format!("{} must yell {}", params.variable, value))
+            }),
+        Roots::None => Err(Error::no_solution(DAY, "the sides are never equal")),
+        Roots::Infinite => Err(Error::no_solution(DAY, "infinitely many solutions")),
     }
 }
 
+pub fn try_part1(input: &str) -> Result<i64, Error> {
+    try_part1_with(input, &Params::default())
+}
+
+pub fn try_part2(input: &str) -> Result<i64, Error> {
+    try_part2_with(input, &Params::default())
+}
+
+pub fn part1(input: &str) -> i64 {
+    try_part1(input).unwrap()
+}
+
+pub fn part2(input: &str) -> i64 {
+    try_part2(input).unwrap()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{equation, part1, part2, try_part1, try_part2, try_part2_with, Params};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "root: pppw + sjmn\n\
                               dbpl: 5\n\
@@ -219,5 +228,48 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 301);
     }
-}
 
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            try_part1("root: pppw % sjmn\n"),
+            Err(Error::Parse {
+                day: 21,
+                line: 1,
+                column: 12,
+                expected: "`+`, `-`, `*` or `/`".to_string()
+            })
+        );
+        assert!(matches!(
+            try_part1("root: pppw + sjmn\npppw: 1\n"),
+            Err(Error::NoSolution { .. })
+        ));
+    }
+
+    #[test]
+    fn test_equation() {
+        let params = Params::default();
+        let equation = equation(TEST_INPUT, &params).unwrap();
+
+        assert_eq!(equation.simplify("humn").unwrap().to_string(), "1/2 * humn - 301/2");
+        assert!(equation.to_string().ends_with("= ((32 - 2) * 5)"));
+
+        // the unknown on the right, and a fractional answer
+        let input = "root: a + humn\na: 3\nhumn: 0\n";
+        assert_eq!(try_part2(input), Ok(3));
+
+        let input = "root: a + b\na: humn * c\nb: 3\nc: 2\nhumn: 0\n";
+        assert_eq!(try_part2(input), Err(Error::no_solution(21, "humn must yell 3/2")));
+
+        let params = Params { root: "a".to_string(), variable: "humn".to_string() };
+        let input = "a: b - c\nb: humn * d\nc: 8\nd: 2\nhumn: 0\n";
+        assert_eq!(try_part2_with(input, &params), Ok(4));
+
+        // `humn - humn` doesn't depend on us at all
+        let input = "root: a + b\na: humn - humn\nb: 0\nhumn: 0\n";
+        assert_eq!(
+            try_part2(input),
+            Err(Error::no_solution(21, "infinitely many solutions"))
+        );
+    }
+}
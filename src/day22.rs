@@ -1,77 +1,233 @@
+use std::collections::HashMap;
 use std::fmt;
 
+use crate::error::{end_of, Error};
+use crate::grid::Grid;
+
+const DAY: u8 = 22;
+
+// right, down, left, up, indexed by facing
+const DIRECTIONS: [(i64, i64); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
 struct Pawn {
     x: i64,
     y: i64,
-    dir: f64,
+    facing: usize,
 }
 
 impl Pawn {
     fn turn(&mut self, dir: char) {
-        // note: the y-axis is mirrored
-        let theta = match dir {
-            'L' => -(1.0/2.0) * std::f64::consts::PI,
-            'R' => (1.0/2.0) * std::f64::consts::PI,
+        // note: the y-axis is mirrored, so turning right is clockwise
+        self.facing = match dir {
+            'L' => (self.facing + 3) % 4,
+            'R' => (self.facing + 1) % 4,
             _ => panic!()
         };
+    }
+
+    fn password(&self) -> i64 {
+        (self.y + 1) * 1000 + 4 * (self.x + 1) + self.facing as i64
+    }
+}
+
+type Vec3 = [i64; 3];
+
+fn neg(v: Vec3) -> Vec3 {
+    [-v[0], -v[1], -v[2]]
+}
+
+// a face of the folded cube, with the 3D directions its net's x and y axes
+// point in and the direction it faces
+#[derive(Clone,Copy)]
+struct Face {
+    origin: (i64, i64),
+    right: Vec3,
+    down: Vec3,
+    normal: Vec3,
+}
+
+impl Face {
+    // the face across the edge in net direction `dir`, once folded
+    fn fold(&self, dir: (i64, i64)) -> Self {
+        let (right, down, normal) = (self.right, self.down, self.normal);
+
+        let (right, down, normal) = match dir {
+            (1, 0) => (neg(normal), down, right),
+            (-1, 0) => (normal, down, neg(right)),
+            (0, 1) => (right, neg(normal), down),
+            _ => (right, normal, neg(down)),
+        };
 
-        self.dir += theta;
+        Self { origin: self.origin, right, down, normal }
     }
 
-    fn facing(&self) -> i64 {
-        ((self.dir / std::f64::consts::PI).rem_euclid(2.0) * 2.0) as i64
+    // the net direction a 3D direction along this face corresponds to
+    fn direction(&self, v: Vec3) -> (i64, i64) {
+        if v == self.right {
+            (1, 0)
+        } else if v == neg(self.right) {
+            (-1, 0)
+        } else if v == self.down {
+            (0, 1)
+        } else {
+            (0, -1)
+        }
     }
 
-    fn password(&self) -> i64 {
-        (self.y + 1) * 1000 + 4 * (self.x + 1) + self.facing()
+    fn vector(&self, dir: (i64, i64)) -> Vec3 {
+        match dir {
+            (1, 0) => self.right,
+            (-1, 0) => neg(self.right),
+            (0, 1) => self.down,
+            _ => neg(self.down),
+        }
+    }
+}
+
+// the map folded up into a cube
+struct Cube {
+    size: i64,
+    faces: HashMap<(i64, i64), Face>,
+}
+
+impl Cube {
+    fn fold(tiles: &Grid<u8>) -> Result<Self, Error> {
+        let not_a_net = || Error::no_solution(DAY, "the map is not a cube net");
+
+        let area = tiles
+            .positions()
+            .filter(|&pos| tiles[pos] != b' ')
+            .count();
+
+        let size = (1..).find(|size| size * size * 6 >= area).unwrap();
+
+        if size * size * 6 != area {
+            return Err(not_a_net());
+        }
+
+        let mut faces = HashMap::new();
+        let mut queue = vec![];
+
+        // start from the first face in the net and walk over its neighbours
+        let start = tiles.position(|&b| b != b' ').unwrap();
+        let start = ((start.0 / size) as i64, (start.1 / size) as i64);
+
+        queue.push(Face {
+            origin: start,
+            right: [1, 0, 0],
+            down: [0, 1, 0],
+            normal: [0, 0, -1],
+        });
+
+        while let Some(face) = queue.pop() {
+            if faces.contains_key(&face.origin) {
+                continue;
+            }
+
+            faces.insert(face.origin, face);
+
+            for dir in DIRECTIONS {
+                let origin = (face.origin.0 + dir.0, face.origin.1 + dir.1);
+                let corner = (origin.0 * size as i64, origin.1 * size as i64);
+
+                if tiles.get(corner).is_some_and(|&b| b != b' ') {
+                    queue.push(Face { origin, ..face.fold(dir) });
+                }
+            }
+        }
+
+        if faces.len() != 6 {
+            return Err(not_a_net());
+        }
+
+        Ok(Self { size: size as i64, faces })
+    }
+
+    // where a pawn at (x, y) facing `dir` ends up when stepping over the
+    // edge of its face, and the direction it faces afterwards
+    fn wrap(&self, (x, y): (i64, i64), dir: (i64, i64)) -> ((i64, i64), (i64, i64)) {
+        let size = self.size;
+        let from = self.faces[&(x / size, y / size)];
+
+        // the face we walk onto faces the way we were heading
+        let heading = from.vector(dir);
+        let to = self
+            .faces
+            .values()
+            .find(|face| face.normal == heading)
+            .unwrap();
+
+        // we walk away from the face we came from
+        let new_dir = to.direction(neg(from.normal));
+
+        // distance along the shared edge, measured in the original face's
+        // axes, and the direction that edge runs in on the new face
+        let (along, edge) = if dir.0 != 0 {
+            (y.rem_euclid(size), to.direction(from.down))
+        } else {
+            (x.rem_euclid(size), to.direction(from.right))
+        };
+
+        let offset = if edge.0 + edge.1 > 0 { along } else { size - 1 - along };
+
+        let (local_x, local_y) = match new_dir {
+            (1, 0) => (0, offset),
+            (-1, 0) => (size - 1, offset),
+            (0, 1) => (offset, 0),
+            _ => (offset, size - 1),
+        };
+
+        let pos = (
+            to.origin.0 * size + local_x,
+            to.origin.1 * size + local_y,
+        );
+
+        (pos, new_dir)
     }
 }
 
 struct Board {
-    tiles: Vec<Vec<u8>>,
+    tiles: Grid<u8>,
     pawn: Pawn,
 }
 
 impl Board {
-    fn from(input: &str) -> Self {
-        let mut tiles: Vec<Vec<u8>> = input
-            .lines()
-            .map(|row| row
-                 .as_bytes()
-                 .to_vec())
-            .collect();
-
-        let max_x = tiles.iter().map(|row| row.len()).max().unwrap();
-
-        for row in tiles.iter_mut() {
-            row.resize(max_x, b' ');
+    // `map` is a slice of `input`
+    fn parse(input: &str, map: &str) -> Result<Self, Error> {
+        if let Some(i) = map.find(|c: char| !" .#\n".contains(c)) {
+            return Err(Error::parse(DAY, input, &map[i..], "` `, `.` or `#`"));
         }
 
-        let start_x = input.chars().position(|c| c == '.').unwrap() as i64;
-        let pawn = Pawn { x: start_x, y: 0, dir: 0.0 };
+        let first_row = map.lines().next().unwrap_or(map);
 
-        Self { tiles, pawn }
+        let start_x = first_row
+            .find('.')
+            .ok_or_else(|| Error::parse(DAY, input, first_row, "an open tile to start on"))?;
+
+        let tiles = Grid::parse(map);
+        let pawn = Pawn { x: start_x as i64, y: 0, facing: 0 };
+
+        Ok(Self { tiles, pawn })
     }
 
     fn move_pawn(&mut self, d: usize) {
-        let tiles = &mut self.tiles;
+        let tiles = &self.tiles;
         let pawn = &mut self.pawn;
 
         for _ in 1..=d {
-            let d_x = pawn.dir.cos() as i64;
-            let d_y = pawn.dir.sin() as i64;
+            let (d_x, d_y) = DIRECTIONS[pawn.facing];
 
             let mut y = (pawn.y + d_y)
-                .rem_euclid(tiles.len() as i64);
+                .rem_euclid(tiles.height() as i64);
             let mut x = (pawn.x + d_x)
-                .rem_euclid(tiles[y as usize].len() as i64);
+                .rem_euclid(tiles.width() as i64);
 
-            let mut next_tile = tiles[y as usize][x as usize];
+            let mut next_tile = tiles[(x as usize, y as usize)];
 
             while next_tile == b' ' {
-                y = (y + d_y).rem_euclid(tiles.len() as i64) ;
-                x = (x + d_x).rem_euclid(tiles[y as usize].len() as i64);
-                next_tile = tiles[y as usize][x as usize];
+                y = (y + d_y).rem_euclid(tiles.height() as i64) ;
+                x = (x + d_x).rem_euclid(tiles.width() as i64);
+                next_tile = tiles[(x as usize, y as usize)];
             }
 
             match next_tile {
@@ -85,122 +241,27 @@ impl Board {
         }
     }
 
-    #[allow(clippy::comparison_chain)]
-    fn move_pawn_cube(&mut self, d: usize) {
-        let tiles = &mut self.tiles;
+    fn move_pawn_cube(&mut self, cube: &Cube, d: usize) {
+        let tiles = &self.tiles;
         let pawn = &mut self.pawn;
 
         for _ in 1..=d {
-            let d_x = pawn.dir.cos() as i64;
-            let d_y = pawn.dir.sin() as i64;
-
-            let mut x = pawn.x + d_x;
-            let mut y = pawn.y + d_y;
-
-            let mut dir = pawn.dir;
-
-            (x, y) = match (pawn.x / 50, pawn.y / 50) {
-                // side 1
-                (1, 0) => {
-                    if x / 50 == 0 {
-                        // moving to side 4
-                        dir -= std::f64::consts::PI;
-                        (0, 149 - pawn.y.rem_euclid(50))
-                    } else if y < 0 {
-                        // moving to side 6
-                        dir = 0.0;
-                        (0, 150 + pawn.x.rem_euclid(50))
-                    } else {
-                        (x, y)
-                    }
-                },
-                // side 2
-                (2, 0) => {
-                    if x / 50 > 2 {
-                        // moving to side 5
-                        dir -= std::f64::consts::PI;
-                        (99, 149 - pawn.y.rem_euclid(50))
-                    } else if y < 0 {
-                        // moving to side 6
-                        (pawn.x.rem_euclid(50), 199)
-                    } else if y / 50 > 0 {
-                        // moving to side 3
-                        dir = std::f64::consts::PI;
-                        (99, 50 + pawn.x.rem_euclid(50))
-                    } else {
-                        (x, y)
-                    }
-                },
-                // side 3
-                (1, 1) => {
-                    if x / 50 < 1 {
-                        // moving to side 4
-                        dir = (1.0/2.0) * std::f64::consts::PI;
-                        (pawn.y.rem_euclid(50), 100)
-                    } else if x / 50 > 1 {
-                        // moving to side 2
-                        dir = -(1.0/2.0) * std::f64::consts::PI;
-                        (100 + pawn.y.rem_euclid(50), 49)
-                    } else {
-                        (x, y)
-                    }
-                },
-                // side 4
-                (0, 2) => {
-                    if x < 0 {
-                        // moving to side 1
-                        dir -= std::f64::consts::PI;
-                        (50, 49 - pawn.y.rem_euclid(50))
-                    } else if y / 50 < 2 {
-                        // moving to side 3
-                        dir = 0.0;
-                        (50, 50 + pawn.x.rem_euclid(50))
-                    } else {
-                        (x, y)
-                    }
-                },
-                // side 5
-                (1, 2) => {
-                    if x / 50 > 1 {
-                        // moving to side 2
-                        dir -= std::f64::consts::PI;
-                        (149, 49 - pawn.y.rem_euclid(50))
-                    } else if y / 50 > 2 {
-                        // moving to side 6
-                        dir = std::f64::consts::PI;
-                        (49, 150 + pawn.x.rem_euclid(50))
-                    } else {
-                        (x, y)
-                    }
-                },
-                // side 6
-                (0, 3) => {
-                    if x < 0 {
-                        // moving to side 1
-                        dir = (1.0/2.0) * std::f64::consts::PI;
-                        (50 + pawn.y.rem_euclid(50), 0)
-                    } else if x / 50 > 0 {
-                        // moving to side 5
-                        dir = -(1.0/2.0) * std::f64::consts::PI;
-                        (50 + pawn.y.rem_euclid(50), 149)
-                    } else if y / 50 > 3 {
-                        // moving to side 2
-                        (100 + pawn.x.rem_euclid(50), 0)
-                    } else {
-                        (x, y)
-                    }
-                },
-                _ => panic!()
+            let dir = DIRECTIONS[pawn.facing];
+            let next = (pawn.x + dir.0, pawn.y + dir.1);
+
+            // faces next to each other in the net are next to each other on
+            // the cube, so only stepping off the net needs folding
+            let ((x, y), dir) = match tiles.get(next) {
+                Some(&b) if b != b' ' => (next, dir),
+                _ => cube.wrap((pawn.x, pawn.y), dir),
             };
 
-            let next_tile = tiles[y as usize][x as usize];
-
-            match next_tile {
+            match tiles[(x as usize, y as usize)] {
                 b'#' => break,
                 b'.' => {
                     pawn.x = x;
                     pawn.y = y;
-                    pawn.dir = dir % (std::f64::consts::PI * 2.0);
+                    pawn.facing = DIRECTIONS.iter().position(|&d| d == dir).unwrap();
                 }
                 _ => panic!()
             }
@@ -210,10 +271,10 @@ impl Board {
 
 impl std::fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for (y, row) in self.tiles.iter().enumerate() {
+        for (y, row) in self.tiles.rows().enumerate() {
             for (x, b) in row.iter().enumerate() {
                 let c = if x as i64 == self.pawn.x && y as i64 == self.pawn.y {
-                    match self.pawn.facing() {
+                    match self.pawn.facing {
                         0 => '>',
                         1 => 'v',
                         2 => '<',
@@ -232,63 +293,82 @@ impl std::fmt::Display for Board {
     }
 }
 
-pub fn part1(input: &str) -> i64 {
-    let mut input_iter = input
-        .split("\n\n");
+enum Step {
+    Forward(usize),
+    Turn(char),
+}
 
-    let mut board = Board::from(input_iter.next().unwrap());
+// the board and the path, which alternates numbers of tiles and turns
+fn parse(input: &str) -> Result<(Board, Vec<Step>), Error> {
+    let (map, path) = input
+        .split_once("\n\n")
+        .ok_or_else(|| Error::parse(DAY, input, end_of(input), "a blank line and a path"))?;
 
-    let instructions = input_iter.next().unwrap().trim();
+    let board = Board::parse(input, map)?;
 
-    let movements = instructions
-        .split(|c: char| c.is_alphabetic())
-        .map(|num| num.parse::<usize>().unwrap());
+    let mut steps = vec![];
+    let mut rest = path.trim();
 
-    let mut turns = instructions
-        .split(|c: char| c.is_numeric())
-        .filter(|turn| !turn.is_empty());
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
 
-    for movement in movements {
-        board.move_pawn(movement);
+        if digits > 0 {
+            let tiles = rest[..digits]
+                .parse()
+                .map_err(|_| Error::parse(DAY, input, rest, "a number of tiles"))?;
 
-        if let Some(turn) = turns.next() {
-            board.pawn.turn(turn.chars().next().unwrap());
+            steps.push(Step::Forward(tiles));
+            rest = &rest[digits..];
+        } else if rest.starts_with(['L', 'R']) {
+            steps.push(Step::Turn(rest.as_bytes()[0] as char));
+            rest = &rest[1..];
+        } else {
+            return Err(Error::parse(DAY, input, rest, "a number, `L` or `R`"));
         }
     }
 
-    board.pawn.password()
+    Ok((board, steps))
 }
 
-pub fn part2(input: &str) -> i64 {
-    let mut input_iter = input
-        .split("\n\n");
-
-    let mut board = Board::from(input_iter.next().unwrap());
-
-    let instructions = input_iter.next().unwrap().trim();
+pub fn try_part1(input: &str) -> Result<i64, Error> {
+    let (mut board, steps) = parse(input)?;
 
-    let movements = instructions
-        .split(|c: char| c.is_alphabetic())
-        .map(|num| num.parse::<usize>().unwrap());
+    for step in steps {
+        match step {
+            Step::Forward(tiles) => board.move_pawn(tiles),
+            Step::Turn(dir) => board.pawn.turn(dir),
+        }
+    }
 
-    let mut turns = instructions
-        .split(|c: char| c.is_numeric())
-        .filter(|turn| !turn.is_empty());
+    Ok(board.pawn.password())
+}
 
-    for movement in movements {
-        board.move_pawn_cube(movement);
+pub fn try_part2(input: &str) -> Result<i64, Error> {
+    let (mut board, steps) = parse(input)?;
+    let cube = Cube::fold(&board.tiles)?;
 
-        if let Some(turn) = turns.next() {
-            board.pawn.turn(turn.chars().next().unwrap());
+    for step in steps {
+        match step {
+            Step::Forward(tiles) => board.move_pawn_cube(&cube, tiles),
+            Step::Turn(dir) => board.pawn.turn(dir),
         }
     }
 
-    board.pawn.password()
+    Ok(board.pawn.password())
+}
+
+pub fn part1(input: &str) -> i64 {
+    try_part1(input).unwrap()
+}
+
+pub fn part2(input: &str) -> i64 {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, try_part1, try_part2, Board, Cube};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "\x20       ...#\n\
                               \x20       .#..\n\
@@ -309,13 +389,68 @@ mod tests {
         assert_eq!(part1(TEST_INPUT), 6032);
     }
     
-    // The solution for part 2 only works for cube nets laid out as (xFF, xFx,
-    // FFx, Fxx). The test case is laid out as (xxFx, FFFx, xxFF), so the test
-    // doesn't work.
-    #[ignore]
     #[test]
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 5031);
     }
-}
 
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            try_part1("garbage\n"),
+            Err(Error::Parse {
+                day: 22,
+                line: 2,
+                column: 1,
+                expected: "a blank line and a path".to_string()
+            })
+        );
+        assert_eq!(
+            try_part1("..\n..\n\n10R5X\n"),
+            Err(Error::Parse {
+                day: 22,
+                line: 4,
+                column: 5,
+                expected: "a number, `L` or `R`".to_string()
+            })
+        );
+        assert_eq!(
+            try_part2("..\n..\n\n10R5\n"),
+            Err(Error::no_solution(22, "the map is not a cube net"))
+        );
+    }
+
+    #[test]
+    fn test_fold() {
+        // the layout of the actual puzzle inputs, with no walls
+        let net = "\x20 ....\n\
+                   \x20 ....\n\
+                   \x20 ..\n\
+                   \x20 ..\n\
+                   ....\n\
+                   ....\n\
+                   ..\n\
+                   ..\n";
+
+        let mut board = Board::parse(net, net).unwrap();
+        let cube = Cube::fold(&board.tiles).unwrap();
+
+        // walking around the cube leads back to where we started
+        for pos in board.tiles.positions() {
+            if board.tiles[pos] == b' ' {
+                continue;
+            }
+
+            for facing in 0..4 {
+                board.pawn.x = pos.0 as i64;
+                board.pawn.y = pos.1 as i64;
+                board.pawn.facing = facing;
+
+                board.move_pawn_cube(&cube, 4 * 2);
+
+                assert_eq!((board.pawn.x, board.pawn.y), (pos.0 as i64, pos.1 as i64));
+                assert_eq!(board.pawn.facing, facing);
+            }
+        }
+    }
+}
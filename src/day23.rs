@@ -1,179 +1,294 @@
-use std::collections::HashMap;
-use std::collections::HashSet;
+use std::fmt;
 
-type Rule = fn(&Board, (i64, i64)) -> Option<(i64, i64)>;
+use crate::error::Error;
 
-struct Board {
-    elves: HashSet<(i64, i64)>,
-    rules: Vec<Rule>,
+const DAY: u8 = 23;
+
+// one row of the board, bit x of the row being column x
+type Row = Vec<u64>;
+
+// bit x of the result is bit x - 1 of `row`
+fn west(row: &[u64]) -> Row {
+    (0..row.len())
+        .map(|i| row[i] << 1 | if i > 0 { row[i - 1] >> 63 } else { 0 })
+        .collect()
+}
+
+// bit x of the result is bit x + 1 of `row`
+fn east(row: &[u64]) -> Row {
+    (0..row.len())
+        .map(|i| row[i] >> 1 | if i + 1 < row.len() { row[i + 1] << 63 } else { 0 })
+        .collect()
+}
+
+fn or(a: &[u64], b: &[u64]) -> Row {
+    a.iter().zip(b).map(|(a, b)| a | b).collect()
+}
+
+fn and_not(a: &[u64], b: &[u64]) -> Row {
+    a.iter().zip(b).map(|(a, b)| a & !b).collect()
+}
+
+fn is_empty(row: &[u64]) -> bool {
+    row.iter().all(|&word| word == 0)
+}
+
+#[derive(Clone, Copy)]
+enum Direction {
+    North,
+    South,
+    West,
+    East,
+}
+
+// elves as one bitmask per row, kept with an empty border so that every
+// neighbour and destination is on the board
+#[derive(Clone)]
+pub struct Board {
+    rows: Vec<Row>,
     rounds: usize,
 }
 
 impl Board {
-    fn from(input: &str) -> Self {
-        let elves: HashSet<(i64, i64)> = input
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        if let Some(i) = input.find(|c: char| !".#\n".contains(c)) {
+            return Err(Error::parse(DAY, input, &input[i..], "`.` or `#`"));
+        }
+
+        let width = input.lines().map(|line| line.len()).max().unwrap_or(0);
+        let words = width / 64 + 1;
+
+        let rows = input
             .lines()
-            .enumerate()
-            .flat_map(|(y, row)| {
+            .map(|line| {
+                let mut row = vec![0; words];
+
+                for (x, _) in line.bytes().enumerate().filter(|&(_, b)| b == b'#') {
+                    row[x / 64] |= 1 << (x % 64);
+                }
+
                 row
-                    .chars()
-                    .enumerate()
-                    .filter_map(move |(x, c)| {
-                        if c == '#' {
-                            Some((x as i64, y as i64))
-                        } else {
-                            None
-                        }
-                    })
             })
             .collect();
 
-        let mut rules: Vec<Rule> = vec![];
-
-        // North rule
-        rules.push(|board: &Board, elf: (i64, i64)| {
-            if board.elves.get(&(elf.0 - 1, elf.1 - 1)).is_none()
-               && board.elves.get(&(elf.0, elf.1 - 1)).is_none()
-               && board.elves.get(&(elf.0 + 1, elf.1 - 1)).is_none() {
-                   Some((elf.0, elf.1 - 1))
-               } else {
-                   None
-               }
-        });
-
-        // South rule
-        rules.push(|board: &Board, elf: (i64, i64)| {
-            if board.elves.get(&(elf.0 - 1, elf.1 + 1)).is_none()
-               && board.elves.get(&(elf.0, elf.1 + 1)).is_none()
-               && board.elves.get(&(elf.0 + 1, elf.1 + 1)).is_none() {
-                   Some((elf.0, elf.1 + 1))
-               } else {
-                   None
-               }
-        });
-
-        // West rule
-        rules.push(|board: &Board, elf: (i64, i64)| {
-            if board.elves.get(&(elf.0 - 1, elf.1 - 1)).is_none()
-               && board.elves.get(&(elf.0 - 1, elf.1)).is_none()
-               && board.elves.get(&(elf.0 - 1, elf.1 + 1)).is_none() {
-                   Some((elf.0 - 1, elf.1))
-               } else {
-                   None
-               }
-        });
-
-        // East rule
-        rules.push(|board: &Board, elf: (i64, i64)| {
-            if board.elves.get(&(elf.0 + 1, elf.1 - 1)).is_none()
-               && board.elves.get(&(elf.0 + 1, elf.1)).is_none()
-               && board.elves.get(&(elf.0 + 1, elf.1 + 1)).is_none() {
-                   Some((elf.0 + 1, elf.1))
-               } else {
-                   None
-               }
-        });
-
-        Self { elves, rules, rounds: 0 }
+        let mut board = Self { rows, rounds: 0 };
+        board.grow();
+
+        Ok(board)
+    }
+
+    fn words(&self) -> usize {
+        self.rows[0].len()
     }
 
-    fn proposed_move(&self, elf: (i64, i64)) -> Option<(i64, i64)> {
-       // don't propose move if elf has no neighbours
-        if self.elves.get(&(elf.0 - 1, elf.1 - 1)).is_none()
-           && self.elves.get(&(elf.0, elf.1 - 1)).is_none()
-           && self.elves.get(&(elf.0 + 1, elf.1 - 1)).is_none()
-           && self.elves.get(&(elf.0 - 1, elf.1)).is_none()
-           && self.elves.get(&(elf.0 + 1, elf.1)).is_none()
-           && self.elves.get(&(elf.0 - 1, elf.1 + 1)).is_none()
-           && self.elves.get(&(elf.0, elf.1 + 1)).is_none()
-           && self.elves.get(&(elf.0 + 1, elf.1 + 1)).is_none() {
-            return None;
+    // make room around the elves for them to move into
+    fn grow(&mut self) {
+        let words = self.rows.first().map_or(1, |row| row.len());
+
+        if self.rows.first().is_none_or(|row| !is_empty(row)) {
+            self.rows.insert(0, vec![0; words]);
         }
 
-        // iterate over rules until a valid move is found
-        for i in 0..self.rules.len() {
-            let j = (i + self.rounds).rem_euclid(self.rules.len());
-            let rule = self.rules[j];
-            
-            if let Some(dest) = rule(self, elf) {
-                return Some(dest);
-            }
+        if !is_empty(self.rows.last().unwrap()) {
+            self.rows.push(vec![0; words]);
         }
 
-        None
+        if self.rows.iter().any(|row| row[0] & 1 != 0) {
+            self.rows.iter_mut().for_each(|row| row.insert(0, 0));
+        }
+
+        if self.rows.iter().any(|row| row[row.len() - 1] >> 63 != 0) {
+            self.rows.iter_mut().for_each(|row| row.push(0));
+        }
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.rounds
     }
 
-    fn play_round(&mut self) {
-        let mut moves: HashMap<(i64, i64), (i64, i64)> = HashMap::new();
-        let mut dest_freq: HashMap<(i64, i64), usize> = HashMap::new();
+    pub fn elves(&self) -> usize {
+        self.rows
+            .iter()
+            .flatten()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
 
-        for elf in self.elves.iter() {
-            if let Some(dest) = self.proposed_move(*elf) {
-                moves.insert(*elf, dest);
+    // plays one round, returning whether any elf moved
+    pub fn step(&mut self) -> bool {
+        let height = self.rows.len();
+        let empty = vec![0; self.words()];
+        let row = |y: usize| self.rows.get(y).unwrap_or(&empty);
 
-                if let Some(freq) = dest_freq.get(&dest) {
-                    dest_freq.insert(dest, freq + 1);
-                } else {
-                    dest_freq.insert(dest, 1);
-                }
+        // the directions in the order this round considers them
+        let order: Vec<Direction> = [
+            Direction::North,
+            Direction::South,
+            Direction::West,
+            Direction::East,
+        ]
+            .iter()
+            .cycle()
+            .skip(self.rounds % 4)
+            .take(4)
+            .copied()
+            .collect();
+
+        // per row, the elves proposing to move north, south, west and east
+        let mut proposals: Vec<[Row; 4]> = vec![Default::default(); height];
+
+        for (y, proposal) in proposals.iter_mut().enumerate() {
+            let (above, here, below) = (row(y.wrapping_sub(1)), row(y), row(y + 1));
+
+            let north = or(&or(above, &west(above)), &east(above));
+            let south = or(&or(below, &west(below)), &east(below));
+            let west_side = or(&or(&west(above), &west(here)), &west(below));
+            let east_side = or(&or(&east(above), &east(here)), &east(below));
+
+            let crowded = or(&or(&north, &south), &or(&west(here), &east(here)));
+            let mut undecided: Row = here
+                .iter()
+                .zip(&crowded)
+                .map(|(here, crowded)| here & crowded)
+                .collect();
+
+            for direction in &order {
+                let (i, blocked) = match direction {
+                    Direction::North => (0, &north),
+                    Direction::South => (1, &south),
+                    Direction::West => (2, &west_side),
+                    Direction::East => (3, &east_side),
+                };
+
+                proposal[i] = and_not(&undecided, blocked);
+                undecided = and_not(&undecided, &proposal[i]);
             }
         }
 
-        let mut new_positions: HashSet<(i64, i64)> = HashSet::new();
+        let proposal = |y: usize, i: usize| {
+            proposals.get(y).map_or(&empty, |proposal| &proposal[i])
+        };
+
+        // only elves proposing opposite moves can meet, so a move goes ahead
+        // unless the elf two tiles on proposes the opposite one
+        let mut moved = false;
+        let mut rows = vec![empty.clone(); height];
 
-        for elf in self.elves.iter() {
-            if let Some(dest) = moves.get(elf) {
-                if *dest_freq.get(dest).unwrap() == 1 {
-                    new_positions.insert(*dest);
-                } else {
-                    new_positions.insert(*elf);
+        for (y, proposal_here) in proposals.iter().enumerate() {
+            let [north, south, west_move, east_move] = proposal_here;
+
+            let moves = [
+                (north, proposal(y.wrapping_sub(2), 1).clone()),
+                (south, proposal(y + 2, 0).clone()),
+                (west_move, west(&west(east_move))),
+                (east_move, east(&east(west_move))),
+            ];
+
+            // elves that don't move
+            let mut stay = row(y).clone();
+
+            for (i, (elves, opposite)) in moves.into_iter().enumerate() {
+                let going = and_not(elves, &opposite);
+
+                // the border rows never move
+                if is_empty(&going) {
+                    continue;
+                }
+
+                stay = and_not(&stay, &going);
+                moved = true;
+
+                match i {
+                    0 => rows[y - 1] = or(&rows[y - 1], &going),
+                    1 => rows[y + 1] = or(&rows[y + 1], &going),
+                    2 => rows[y] = or(&rows[y], &east(&going)),
+                    _ => rows[y] = or(&rows[y], &west(&going)),
                 }
-            } else {
-                new_positions.insert(*elf);
             }
+
+            rows[y] = or(&rows[y], &stay);
         }
 
-        self.elves = new_positions;
+        self.rows = rows;
         self.rounds += 1;
+        self.grow();
+
+        moved
     }
 
-    fn ground_tiles(&self) -> usize {
-        let min_x = self.elves.iter().map(|elf| elf.0).min().unwrap();
-        let max_x = self.elves.iter().map(|elf| elf.0).max().unwrap() + 1;
-        let min_y = self.elves.iter().map(|elf| elf.1).min().unwrap();
-        let max_y = self.elves.iter().map(|elf| elf.1).max().unwrap() + 1;
+    // the rows and columns the elves span, as (min, max) inclusive
+    fn bounds(&self) -> Option<((usize, usize), (usize, usize))> {
+        let occupied = |row: &Row| !is_empty(row);
+
+        let min_y = self.rows.iter().position(occupied)?;
+        let max_y = self.rows.iter().rposition(occupied)?;
 
-        (max_x - min_x) as usize * (max_y - min_y) as usize - self.elves.len()
+        let columns = self.rows
+            .iter()
+            .fold(vec![0; self.words()], |acc, row| or(&acc, row));
+        let first = columns.iter().position(|&word| word != 0)?;
+        let last = columns.iter().rposition(|&word| word != 0)?;
+
+        let min_x = first * 64 + columns[first].trailing_zeros() as usize;
+        let max_x = last * 64 + 63 - columns[last].leading_zeros() as usize;
+
+        Some(((min_x, min_y), (max_x, max_y)))
+    }
+
+    pub fn empty_ground(&self) -> usize {
+        match self.bounds() {
+            Some((min, max)) => (max.0 - min.0 + 1) * (max.1 - min.1 + 1) - self.elves(),
+            None => 0,
+        }
     }
 }
 
-pub fn part1(input: &str) -> usize {
-    let mut board = Board::from(input);
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((min, max)) = self.bounds() {
+            for row in &self.rows[min.1..=max.1] {
+                for x in min.0..=max.0 {
+                    let elf = row[x / 64] & (1 << (x % 64)) != 0;
+                    write!(f, "{}", if elf { '#' } else { '.' })?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn try_part1(input: &str) -> Result<usize, Error> {
+    let mut board = Board::parse(input)?;
 
     for _ in 0..10 {
-        board.play_round();
+        board.step();
     }
 
-    board.ground_tiles()
+    Ok(board.empty_ground())
 }
 
-pub fn part2(input: &str) -> usize {
-    let mut board = Board::from(input);
+pub fn try_part2(input: &str) -> Result<usize, Error> {
+    let mut board = Board::parse(input)?;
 
-    let mut prev_round = board.elves.clone();
-    board.play_round();
+    while board.step() {}
 
-    while board.elves != prev_round {
-        prev_round = board.elves.clone();
-        board.play_round();
-    }
+    Ok(board.rounds())
+}
 
-    board.rounds
+pub fn part1(input: &str) -> usize {
+    try_part1(input).unwrap()
+}
+
+pub fn part2(input: &str) -> usize {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, try_part1, Board};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "....#..\n\
                               ..###.#\n\
@@ -187,10 +302,36 @@ mod tests {
     fn test_part1() {
         assert_eq!(part1(TEST_INPUT), 110);
     }
-    
+
     #[test]
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 20);
     }
-}
 
+    #[test]
+    fn test_step() {
+        let mut board = Board::parse(".....\n..##.\n..#..\n.....\n..##.\n.....\n").unwrap();
+
+        assert!(board.step());
+        assert_eq!(board.to_string(), "##\n..\n#.\n.#\n#.\n");
+        assert!(board.step());
+        assert_eq!(board.to_string(), ".##.\n#...\n...#\n....\n.#..\n");
+        assert!(board.step());
+        assert_eq!(board.to_string(), "..#..\n....#\n#....\n....#\n.....\n..#..\n");
+        assert!(!board.step());
+        assert_eq!((board.rounds(), board.elves()), (4, 5));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            try_part1("..#\n.x.\n"),
+            Err(Error::Parse {
+                day: 23,
+                line: 2,
+                column: 2,
+                expected: "`.` or `#`".to_string()
+            })
+        );
+    }
+}
@@ -1,176 +1,220 @@
-use std::collections::HashMap;
-use std::collections::hash_map::Entry::Vacant;
-use std::collections::HashSet;
-use std::collections::VecDeque;
-
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right
+use crate::error::{end_of, Error};
+use crate::grid;
+use crate::pathfinding;
+
+const DAY: u8 = 24;
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
 }
 
-struct Blizzard {
-    start: (i64, i64),
-    direction: Direction,
+// a bitmask of tiles along a row or column of the interior, bit i being tile i
+type Line = Vec<u64>;
+
+fn empty_line(len: usize) -> Line {
+    vec![0; len / 64 + 1]
 }
 
-impl Blizzard {
-    fn pos(&self, minute: usize) -> (i64, i64) {
-        match self.direction {
-            Direction::Up => (self.start.0, self.start.1 - minute as i64),
-            Direction::Down => (self.start.0, self.start.1 + minute as i64),
-            Direction::Left => (self.start.0 - minute as i64, self.start.1),
-            Direction::Right => (self.start.0 + minute as i64, self.start.1),
-        }
-    }
+fn set(line: &mut [u64], i: usize) {
+    line[i / 64] |= 1 << (i % 64);
+}
+
+fn bit(line: &[u64], i: usize) -> bool {
+    line[i / 64] >> (i % 64) & 1 != 0
 }
 
-struct Grid {
+// the valley's blizzards as bitmasks over the interior: per row for blizzards
+// moving sideways and per column for those moving up or down. each blizzard
+// only ever stays in its row or column, so where it is at a given minute
+// follows from where it started.
+struct Valley {
     start: (i64, i64),
     end: (i64, i64),
+    // size of the interior, without walls
     width: usize,
     height: usize,
-    blizzards: Vec<Blizzard>,
-    blizzard_cache: HashMap<usize, HashSet<(i64, i64)>>
+    left: Vec<Line>,
+    right: Vec<Line>,
+    up: Vec<Line>,
+    down: Vec<Line>,
+    // the blizzards repeat after this many minutes
+    period: usize,
 }
 
-impl Grid {
-    fn from(input: &str) -> Self {
-        let rows: Vec<&str> = input
-            .lines()
-            .collect();
-
-        let start = (input.find('.').unwrap() as i64, 0);
-        let end = (
-            rows.last().unwrap().rfind('.').unwrap() as i64,
-            rows.len() as i64 - 1
-        );
-
-        let width = rows.first().unwrap().len();
-        let height = (end.1 - start.1) as usize + 1;
+impl Valley {
+    // blizzards inside walls, with an opening in the top and bottom rows
+    fn parse(input: &str) -> Result<Self, Error> {
+        let rows: Vec<&str> = input.trim_end().lines().collect();
+        let row_len = rows.first().map_or(0, |row| row.len());
 
-        let mut blizzards = vec![];
+        if rows.len() < 3 || row_len < 3 {
+            let at = end_of(input.trim_end());
+            return Err(Error::parse(DAY, input, at, "a valley with walls around it"));
+        }
 
         for (y, row) in rows.iter().enumerate() {
-            for (x, c) in row.chars().enumerate() {
-                if c == '#' || c == '.' {
-                    continue;
-                }
-
-                let direction = match c {
-                    '^' => Direction::Up,
-                    'v' => Direction::Down,
-                    '<' => Direction::Left,
-                    '>' => Direction::Right,
-                    _ => panic!(),
-                };
+            if row.len() != row_len {
+                let at = row.get(row_len..).unwrap_or(end_of(row));
+                return Err(Error::parse(DAY, input, at, "rows of equal length"));
+            }
 
-                let blizzard = Blizzard {
-                    start: (x as i64, y as i64),
-                    direction 
-                };
+            for (x, c) in row.char_indices() {
+                let wall = x == 0 || x == row_len - 1 || y == 0 || y == rows.len() - 1;
 
-                blizzards.push(blizzard);
+                match (wall, c) {
+                    (true, '#' | '.') | (false, '.' | '<' | '>' | '^' | 'v') => (),
+                    (true, _) => return Err(Error::parse(DAY, input, &row[x..], "`#` or `.`")),
+                    (false, _) => {
+                        return Err(Error::parse(DAY, input, &row[x..], "`.` or a blizzard"));
+                    },
+                }
             }
         }
 
-        let blizzard_cache = HashMap::new();
+        let opening = |row: &str, i: Option<usize>| {
+            i.ok_or_else(|| Error::parse(DAY, input, row, "an opening"))
+        };
 
-        Self { start, end, width, height, blizzards, blizzard_cache }
-    }
+        let last_row = rows.len() - 1;
+        let start = opening(rows[0], rows[0].find('.'))?;
+        let end = opening(rows[last_row], rows[last_row].rfind('.'))?;
 
-    // are we still on the map or have we hit a wall?
-    fn within_bounds(&self, square: (i64, i64)) -> bool {
-        square.0 > 0
-            && square.0 < self.width as i64 - 1
-            && (square.1 > 0 || square == self.start)
-            && (square.1 < self.height as i64 - 1 || square == self.end)
-    }
+        let map = grid::Grid::parse(input.trim_end());
 
-    fn traverse(
-        &mut self,
-        start: (i64, i64),
-        end: (i64, i64),
-        start_minute: usize
-    ) -> Result<usize, ()> {
+        let start = (start as i64, 0);
+        let end = (end as i64, last_row as i64);
 
-        let start_node = (start, start_minute);
+        let width = map.width() - 2;
+        let height = map.height() - 2;
 
-        let mut queue: VecDeque<((i64, i64), usize)> = VecDeque::new();
-        queue.push_back(start_node);
+        let mut left = vec![empty_line(width); height];
+        let mut right = vec![empty_line(width); height];
+        let mut up = vec![empty_line(height); width];
+        let mut down = vec![empty_line(height); width];
 
-        let mut visited = HashSet::new();
-        visited.insert(start_node);
+        for (x, y) in map.positions() {
+            // interior coordinates
+            let (i, j) = (x.wrapping_sub(1), y.wrapping_sub(1));
 
-        while let Some(node) = queue.pop_front() {
-            if node.0 == end {
-                return Ok(node.1);
-            }
+            match map[(x, y)] {
+                b'<' => set(&mut left[j], i),
+                b'>' => set(&mut right[j], i),
+                b'^' => set(&mut up[i], j),
+                b'v' => set(&mut down[i], j),
+                _ => continue,
+            };
+        }
 
-            // determine next minute's blizzards, if we haven't already done so
-            if let Vacant(e) = self.blizzard_cache.entry(node.1 + 1) {
-                let mut next_blizzards = HashSet::new();
+        let period = width * height / gcd(width, height);
 
-                for blizzard in &self.blizzards {
-                    let mut blizzard_pos = blizzard.pos(node.1 + 1);
+        Ok(Self { start, end, width, height, left, right, up, down, period })
+    }
 
-                    blizzard_pos.0 = (blizzard_pos.0 - 1)
-                        .rem_euclid(self.width as i64 - 2) + 1;
-                    blizzard_pos.1 = (blizzard_pos.1 - 1)
-                        .rem_euclid(self.height as i64 - 2) + 1;
-                    next_blizzards.insert(blizzard_pos);
-                }
+    // is the tile free of walls and blizzards at `minute`? a blizzard moving
+    // right is at x after t minutes iff it started at x - t (mod the width),
+    // so rather than store every minute's occupancy, look back to the start
+    fn is_free(&self, pos: (i64, i64), minute: usize) -> bool {
+        if pos == self.start || pos == self.end {
+            return true;
+        }
 
-                e.insert(next_blizzards);
-            }
+        let (w, h) = (self.width as i64, self.height as i64);
+        let (x, y) = (pos.0 - 1, pos.1 - 1);
 
-            let wait = (node.0, node.1 + 1);
-            let up = ((node.0.0, node.0.1 - 1), node.1 + 1);
-            let down = ((node.0.0, node.0.1 + 1), node.1 + 1);
-            let left = ((node.0.0 - 1, node.0.1), node.1 + 1);
-            let right = ((node.0.0 + 1, node.0.1), node.1 + 1);
-
-            let next_nodes = [wait, up, down, left, right];
-
-            for next in next_nodes.iter() {
-                if self.within_bounds(next.0)
-                   && !visited.contains(next)
-                   && !self
-                       .blizzard_cache
-                       .get(&next.1)
-                       .unwrap()
-                       .contains(&next.0) {
-                    queue.push_back(*next);
-                    visited.insert(*next);
-                }
-            }
+        if x < 0 || x >= w || y < 0 || y >= h {
+            return false;
         }
 
-        Err(())
+        let t = minute as i64;
+        let (row, column) = (y as usize, x as usize);
+
+        !(bit(&self.right[row], (x - t).rem_euclid(w) as usize)
+            || bit(&self.left[row], (x + t).rem_euclid(w) as usize)
+            || bit(&self.down[column], (y - t).rem_euclid(h) as usize)
+            || bit(&self.up[column], (y + t).rem_euclid(h) as usize))
+    }
+
+    // the minute we reach `to` at the earliest, setting off from `from` at
+    // `start_minute`
+    fn traverse(
+        &self,
+        from: (i64, i64),
+        to: (i64, i64),
+        start_minute: usize
+    ) -> Option<usize> {
+        // states repeat with the blizzards, so only the minute within the
+        // period tells them apart
+        let neighbours = |&(pos, phase): &((i64, i64), usize)| {
+            let next_phase = (phase + 1) % self.period;
+
+            let wait = pos;
+            let up = (pos.0, pos.1 - 1);
+            let down = (pos.0, pos.1 + 1);
+            let left = (pos.0 - 1, pos.1);
+            let right = (pos.0 + 1, pos.1);
+
+            [wait, up, down, left, right]
+                .into_iter()
+                .filter(move |&next| self.is_free(next, next_phase))
+                .map(move |next| ((next, next_phase), 1))
+        };
+
+        let heuristic = |&(pos, _): &((i64, i64), usize)| {
+            (pos.0.abs_diff(to.0) + pos.1.abs_diff(to.1)) as usize
+        };
+
+        let path = pathfinding::astar(
+            (from, start_minute % self.period),
+            neighbours,
+            heuristic,
+            |&(pos, _)| pos == to
+        )?;
+
+        Some(start_minute + path.cost)
+    }
+
+    // the minute the last leg ends, walking between `waypoints` in turn
+    fn journey(&self, waypoints: &[(i64, i64)]) -> Option<usize> {
+        waypoints
+            .windows(2)
+            .try_fold(0, |minute, leg| self.traverse(leg[0], leg[1], minute))
     }
 }
 
-pub fn part1(input: &str) -> usize {
-    let mut mountain = Grid::from(input);
+// minutes to cross the valley `legs` times, going back and forth between the
+// entrance and the exit
+pub fn trips(input: &str, legs: usize) -> Result<usize, Error> {
+    let valley = Valley::parse(input)?;
+
+    let waypoints: Vec<_> = (0..=legs)
+        .map(|leg| if leg % 2 == 0 { valley.start } else { valley.end })
+        .collect();
 
-    mountain.traverse(mountain.start, mountain.end, 0).unwrap()
+    valley
+        .journey(&waypoints)
+        .ok_or_else(|| Error::no_solution(DAY, "the blizzards block every way through"))
 }
 
-#[allow(clippy::let_and_return)]
-pub fn part2(input: &str) -> usize {
-    let mut mountain = Grid::from(input);
+pub fn try_part1(input: &str) -> Result<usize, Error> {
+    trips(input, 1)
+}
 
-    let there = mountain.traverse(mountain.start, mountain.end, 0).unwrap();
-    let back = mountain.traverse(mountain.end, mountain.start, there).unwrap();
-    let again = mountain.traverse(mountain.start, mountain.end, back).unwrap();
+pub fn try_part2(input: &str) -> Result<usize, Error> {
+    trips(input, 3)
+}
+
+pub fn part1(input: &str) -> usize {
+    try_part1(input).unwrap()
+}
 
-    again
+pub fn part2(input: &str) -> usize {
+    try_part2(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, trips, try_part1};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "#.######\n\
                               #>>.<^<#\n\
@@ -188,5 +232,45 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(TEST_INPUT), 54);
     }
-}
 
+    #[test]
+    fn test_trips() {
+        assert_eq!(trips(TEST_INPUT, 0), Ok(0));
+        assert_eq!(trips(TEST_INPUT, 2), Ok(18 + 23));
+        assert!(trips(TEST_INPUT, 5).unwrap() > 54);
+
+        // valleys wider than a word, with one blizzard circling the top row
+        let width = 130;
+        let wide = format!(
+            "#.{}\n#>{}#\n#{}#\n{}.#\n",
+            "#".repeat(width),
+            ".".repeat(width - 1),
+            ".".repeat(width),
+            "#".repeat(width)
+        );
+
+        assert_eq!(trips(&wide, 1), Ok(width + 2));
+
+        // a blizzard that never leaves the only way through
+        assert_eq!(
+            trips("#.#\n#^#\n#.#\n", 1),
+            Err(Error::no_solution(24, "the blizzards block every way through"))
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            try_part1("#.###\n#.x.#\n###.#\n"),
+            Err(Error::Parse {
+                day: 24,
+                line: 2,
+                column: 3,
+                expected: "`.` or a blizzard".to_string()
+            })
+        );
+        assert!(try_part1("#.###\n>...#\n###.#\n").is_err());
+        assert!(try_part1("#####\n#...#\n###.#\n").is_err());
+        assert!(try_part1("#.#\n").is_err());
+    }
+}
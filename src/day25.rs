@@ -1,69 +1,261 @@
-pub fn part1(input: &str) -> String {
-    let mut sum = input
-        .lines()
-        .map(|snafu| {
-            snafu
-                .chars()
-                .rev()
-                .enumerate()
-                .map(|(i, c)| {
-                    (match c {
-                        '=' => -2,
-                        '-' => -1,
-                        '0' => 0,
-                        '1' => 1,
-                        '2' => 2,
-                        _ => panic!(),
-                    }) * 5i64.pow(i as u32)
+use std::fmt;
+use std::hash::Hash;
+use std::iter::Sum;
+use std::marker::PhantomData;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
+use crate::error::Error;
+
+const DAY: u8 = 25;
+
+// the symbols of a balanced number system, one per digit from the most
+// negative up. there must be an odd number of them, the middle one being 0.
+pub trait Digits: Copy + Eq + Ord + Hash + fmt::Debug {
+    const SYMBOLS: &'static [u8];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SnafuDigits;
+
+impl Digits for SnafuDigits {
+    const SYMBOLS: &'static [u8] = b"=-012";
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalancedError {
+    Empty,
+    // the byte offset and the character found there
+    InvalidDigit(usize, char),
+    Overflow,
+}
+
+impl fmt::Display for BalancedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BalancedError::Empty => write!(f, "no digits"),
+            BalancedError::InvalidDigit(offset, c) => {
+                write!(f, "invalid digit `{}` at offset {}", c, offset)
+            },
+            BalancedError::Overflow => write!(f, "number too large"),
+        }
+    }
+}
+
+impl std::error::Error for BalancedError {}
+
+// an integer in a balanced number system, in which digits go from -n to n and
+// the base is 2n + 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Balanced<D: Digits> {
+    value: i128,
+    digits: PhantomData<D>,
+}
+
+pub type Snafu = Balanced<SnafuDigits>;
+
+impl<D: Digits> Balanced<D> {
+    pub const ZERO: Self = Self::new(0);
+
+    const BASE: i128 = D::SYMBOLS.len() as i128;
+    const MAX_DIGIT: i128 = Self::BASE / 2;
+
+    pub const fn new(value: i128) -> Self {
+        Self { value, digits: PhantomData }
+    }
+
+    pub fn value(&self) -> i128 {
+        self.value
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.value.checked_add(other.value).map(Self::new)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.value.checked_sub(other.value).map(Self::new)
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        self.value.checked_mul(other.value).map(Self::new)
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        self.value.checked_neg().map(Self::new)
+    }
+
+    fn digit(symbol: u8) -> Option<i128> {
+        D::SYMBOLS
+            .iter()
+            .position(|&s| s == symbol)
+            .map(|i| i as i128 - Self::MAX_DIGIT)
+    }
+
+    fn symbol(digit: i128) -> char {
+        D::SYMBOLS[(digit + Self::MAX_DIGIT) as usize] as char
+    }
+}
+
+impl<D: Digits> FromStr for Balanced<D> {
+    type Err = BalancedError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(BalancedError::Empty);
+        }
+
+        let mut value: i128 = 0;
+
+        for (i, c) in s.char_indices() {
+            let digit = u8::try_from(c)
+                .ok()
+                .and_then(Self::digit)
+                .ok_or(BalancedError::InvalidDigit(i, c))?;
+
+            // near the limits, shifting may overflow where the next digit
+            // would pull the value back in range, so borrow one from the
+            // shifted value when that happens
+            let borrow = value.signum();
+
+            value = value
+                .checked_mul(Self::BASE)
+                .and_then(|shifted| shifted.checked_add(digit))
+                .or_else(|| {
+                    (value - borrow)
+                        .checked_mul(Self::BASE)?
+                        .checked_add(digit + borrow * Self::BASE)
                 })
-                .sum::<i64>()
-        })
-        .sum::<i64>();
-
-        let mut snafu = vec![];
-        let mut i = 1;
-
-        while sum != 0 {
-            let num = sum % 5i64.pow(i);
-            sum -= num;
-            snafu.push(num / 5i64.pow(i - 1));
-            i += 1;
+                .ok_or(BalancedError::Overflow)?;
+        }
+
+        Ok(Self::new(value))
+    }
+}
+
+impl<D: Digits> fmt::Display for Balanced<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.value == 0 {
+            return write!(f, "{}", Self::symbol(0));
         }
 
-        for i in 0..snafu.len() {
-            let carry = (snafu[i] + 2) / 5;
-            let remainder = snafu[i] - 5 * carry;
-
-            snafu[i] = remainder;
-            
-            if carry != 0 {
-                if i + 1 < snafu.len() {
-                    snafu[i + 1] += carry;
-                } else {
-                    snafu.push(carry);
-                }
+        let mut digits = vec![];
+        let mut value = self.value;
+
+        // digits above the largest one carry over to the next place
+        while value != 0 {
+            let mut digit = value.rem_euclid(Self::BASE);
+            value = value.div_euclid(Self::BASE);
+
+            if digit > Self::MAX_DIGIT {
+                digit -= Self::BASE;
+                value += 1;
             }
+
+            digits.push(Self::symbol(digit));
         }
 
-        snafu
-            .iter()
-            .rev()
-            .map(|num| {
-                match num {
-                    -2 => '=',
-                    -1 => '-',
-                    0 => '0',
-                    1 => '1',
-                    2 => '2',
-                    _ => panic!(),
-                }
-            })
-            .collect::<String>()
+        write!(f, "{}", digits.iter().rev().collect::<String>())
+    }
+}
+
+impl<D: Digits> From<i64> for Balanced<D> {
+    fn from(n: i64) -> Self {
+        Self::new(n as i128)
+    }
+}
+
+impl<D: Digits> From<i128> for Balanced<D> {
+    fn from(n: i128) -> Self {
+        Self::new(n)
+    }
+}
+
+impl<D: Digits> TryFrom<Balanced<D>> for i64 {
+    type Error = BalancedError;
+
+    fn try_from(n: Balanced<D>) -> Result<Self, Self::Error> {
+        i64::try_from(n.value).map_err(|_| BalancedError::Overflow)
+    }
+}
+
+impl<D: Digits> From<Balanced<D>> for i128 {
+    fn from(n: Balanced<D>) -> Self {
+        n.value
+    }
+}
+
+impl<D: Digits> Add for Balanced<D> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.checked_add(other).expect("attempt to add with overflow")
+    }
+}
+
+impl<D: Digits> Sub for Balanced<D> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self.checked_sub(other).expect("attempt to subtract with overflow")
+    }
+}
+
+impl<D: Digits> Mul for Balanced<D> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        self.checked_mul(other).expect("attempt to multiply with overflow")
+    }
+}
+
+impl<D: Digits> Neg for Balanced<D> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.checked_neg().expect("attempt to negate with overflow")
+    }
+}
+
+impl<D: Digits> Sum for Balanced<D> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+
+impl<'a, D: Digits> Sum<&'a Balanced<D>> for Balanced<D> {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+pub fn try_part1(input: &str) -> Result<String, Error> {
+    let mut sum = Snafu::ZERO;
+
+    for line in input.lines() {
+        let snafu: Snafu = line.parse().map_err(|e| match e {
+            BalancedError::InvalidDigit(i, _) => {
+                Error::parse(DAY, input, &line[i..], "a SNAFU digit")
+            },
+            BalancedError::Empty => Error::parse(DAY, input, line, "a SNAFU digit"),
+            BalancedError::Overflow => Error::parse(DAY, input, line, "a smaller SNAFU number"),
+        })?;
+
+        sum = sum
+            .checked_add(snafu)
+            .ok_or_else(|| Error::no_solution(DAY, "the sum is too large"))?;
+    }
+
+    Ok(sum.to_string())
+}
+
+pub fn part1(input: &str) -> String {
+    try_part1(input).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::part1;
+    use super::{part1, try_part1, Balanced, BalancedError, Digits, Snafu};
+    use crate::error::Error;
 
     const TEST_INPUT: &str = "1=-0-2\n\
                               12111\n\
@@ -83,5 +275,68 @@ mod tests {
     fn test_part1() {
         assert_eq!(part1(TEST_INPUT), "2=-1=0");
     }
-}
 
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            try_part1("1=-0-2\n12311\n"),
+            Err(Error::Parse {
+                day: 25,
+                line: 2,
+                column: 3,
+                expected: "a SNAFU digit".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_snafu() {
+        let decimal: [i64; 15] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 15, 20, 2022, 12345, 314159265];
+        let snafu = [
+            "1", "2", "1=", "1-", "10", "11", "12", "2=", "2-", "20", "1=0", "1-0",
+            "1=11-2", "1-0---0", "1121-1110-1=0",
+        ];
+
+        for (&n, &s) in decimal.iter().zip(&snafu) {
+            assert_eq!(Snafu::from(n).to_string(), s);
+            assert_eq!(s.parse::<Snafu>().map(i64::try_from), Ok(Ok(n)));
+        }
+
+        let a: Snafu = "2=".parse().unwrap();
+        let b: Snafu = "1-".parse().unwrap();
+
+        assert_eq!((a + b).to_string(), "22");
+        assert_eq!((a - b).to_string(), "1-");
+        assert_eq!((a * b).to_string(), "112");
+        assert_eq!((-a).to_string(), "=2");
+        assert_eq!([a, b, -b].iter().sum::<Snafu>(), a);
+        assert_eq!(Snafu::ZERO.to_string(), "0");
+
+        assert_eq!("".parse::<Snafu>(), Err(BalancedError::Empty));
+        assert_eq!("1=3".parse::<Snafu>(), Err(BalancedError::InvalidDigit(2, '3')));
+        assert_eq!("2".repeat(60).parse::<Snafu>(), Err(BalancedError::Overflow));
+        assert_eq!(i64::try_from(Snafu::from(i128::MAX)), Err(BalancedError::Overflow));
+        assert_eq!(i128::from(Snafu::from(i128::MAX)), i128::MAX);
+
+        for n in [i128::MIN, i128::MAX] {
+            let snafu = Snafu::from(n);
+            assert_eq!(snafu.to_string().parse(), Ok(snafu));
+        }
+    }
+
+    #[test]
+    fn test_balanced_ternary() {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        struct Ternary;
+
+        impl Digits for Ternary {
+            const SYMBOLS: &'static [u8] = b"-0+";
+        }
+
+        let n = Balanced::<Ternary>::from(8i64);
+
+        assert_eq!(n.to_string(), "+0-");
+        assert_eq!((-n).to_string(), "-0+");
+        assert_eq!("++".parse::<Balanced<Ternary>>(), Ok(Balanced::from(4i64)));
+    }
+}
@@ -0,0 +1,95 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    // input does not match the puzzle format
+    Parse {
+        day: u8,
+        line: usize,
+        column: usize,
+        expected: String,
+    },
+    // input is well-formed but has no answer
+    NoSolution {
+        day: u8,
+        reason: String,
+    },
+    // a parameter override the day doesn't accept
+    Param {
+        day: u8,
+        name: String,
+        reason: String,
+    },
+}
+
+impl Error {
+    // `at` must be a slice of `input`; an empty `at` marks where the parser
+    // ran out of input
+    pub fn parse(day: u8, input: &str, at: &str, expected: &str) -> Self {
+        let offset = (at.as_ptr() as usize)
+            .saturating_sub(input.as_ptr() as usize)
+            .min(input.len());
+
+        let before = &input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rfind('\n')
+            .map_or(before, |newline| &before[newline + 1..])
+            .chars()
+            .count() + 1;
+
+        Error::Parse { day, line, column, expected: expected.to_string() }
+    }
+
+    pub fn no_solution(day: u8, reason: &str) -> Self {
+        Error::NoSolution { day, reason: reason.to_string() }
+    }
+
+    pub fn param(day: u8, name: &str, reason: &str) -> Self {
+        Error::Param { day, name: name.to_string(), reason: reason.to_string() }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse { day, line, column, expected } => write!(
+                f,
+                "day {}: parse error at line {}, column {}: expected {}",
+                day, line, column, expected
+            ),
+            Error::NoSolution { day, reason } => {
+                write!(f, "day {}: no solution: {}", day, reason)
+            },
+            Error::Param { day, name, reason } => {
+                write!(f, "day {}: invalid parameter `{}`: {}", day, name, reason)
+            },
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// the empty slice just past the end of `s`, for reporting truncated input
+pub fn end_of(s: &str) -> &str {
+    &s[s.len()..]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{end_of, Error};
+
+    #[test]
+    fn test_position() {
+        let input = "abc\ndéf\nghi";
+
+        assert_eq!(
+            Error::parse(1, input, &input[7..], "a digit"),
+            Error::Parse { day: 1, line: 2, column: 3, expected: "a digit".to_string() }
+        );
+        assert_eq!(
+            Error::parse(1, input, end_of(input), "more input").to_string(),
+            "day 1: parse error at line 3, column 4: expected more input"
+        );
+    }
+}
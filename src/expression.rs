@@ -0,0 +1,335 @@
+// Arithmetic expression trees, evaluated exactly with fractions. Expressions
+// that are linear in one variable can be simplified to `a * x + b`, which is
+// enough to solve equations like day 21's.
+
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+// always in lowest terms, with a positive denominator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+impl Rational {
+    pub const ZERO: Self = Self { num: 0, den: 1 };
+    pub const ONE: Self = Self { num: 1, den: 1 };
+
+    // panics if `den` is zero
+    pub fn new(num: i128, den: i128) -> Self {
+        assert!(den != 0, "zero denominator");
+
+        let d = gcd(num, den) * den.signum();
+
+        Self { num: num / d, den: den / d }
+    }
+
+    pub fn numerator(&self) -> i128 {
+        self.num
+    }
+
+    pub fn denominator(&self) -> i128 {
+        self.den
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    pub fn to_integer(&self) -> Option<i128> {
+        if self.den == 1 { Some(self.num) } else { None }
+    }
+
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        if other.is_zero() { None } else { Some(self / other) }
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(n: i64) -> Self {
+        Self { num: n as i128, den: 1 }
+    }
+}
+
+impl Add for Rational {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.num * other.den + other.num * self.den, self.den * other.den)
+    }
+}
+
+impl Sub for Rational {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Mul for Rational {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::new(self.num * other.num, self.den * other.den)
+    }
+}
+
+impl Div for Rational {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        Self::new(self.num * other.den, self.den * other.num)
+    }
+}
+
+impl Neg for Rational {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self { num: -self.num, den: self.den }
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Operator {
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        match symbol {
+            "+" => Some(Operator::Add),
+            "-" => Some(Operator::Sub),
+            "*" => Some(Operator::Mul),
+            "/" => Some(Operator::Div),
+            _ => None,
+        }
+    }
+
+    pub fn symbol(&self) -> char {
+        match self {
+            Operator::Add => '+',
+            Operator::Sub => '-',
+            Operator::Mul => '*',
+            Operator::Div => '/',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Number(i64),
+    Variable(String),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn binary(operator: Operator, left: Expr, right: Expr) -> Self {
+        Expr::Binary(operator, Box::new(left), Box::new(right))
+    }
+
+    // the value of an expression without variables
+    pub fn eval(&self) -> Result<Rational, String> {
+        match self {
+            Expr::Number(n) => Ok((*n).into()),
+            Expr::Variable(name) => Err(format!("unknown variable `{}`", name)),
+            Expr::Binary(operator, left, right) => {
+                let (l, r) = (left.eval()?, right.eval()?);
+
+                match operator {
+                    Operator::Add => Ok(l + r),
+                    Operator::Sub => Ok(l - r),
+                    Operator::Mul => Ok(l * r),
+                    Operator::Div => {
+                        l.checked_div(r).ok_or_else(|| "division by zero".to_string())
+                    },
+                }
+            },
+        }
+    }
+
+    // rewrite as `a * variable + b`, failing if that isn't possible
+    pub fn linear(&self, variable: &str) -> Result<Linear, String> {
+        match self {
+            Expr::Number(n) => Ok(Linear::constant((*n).into(), variable)),
+            Expr::Variable(name) if name == variable => Ok(Linear {
+                variable: variable.to_string(),
+                a: Rational::ONE,
+                b: Rational::ZERO,
+            }),
+            Expr::Variable(name) => Err(format!("unknown variable `{}`", name)),
+            Expr::Binary(operator, left, right) => {
+                let (l, r) = (left.linear(variable)?, right.linear(variable)?);
+
+                let (a, b) = match operator {
+                    Operator::Add => (l.a + r.a, l.b + r.b),
+                    Operator::Sub => (l.a - r.a, l.b - r.b),
+                    Operator::Mul if l.a.is_zero() => (l.b * r.a, l.b * r.b),
+                    Operator::Mul if r.a.is_zero() => (l.a * r.b, l.b * r.b),
+                    Operator::Div if r.a.is_zero() => {
+                        let divide = |n: Rational| {
+                            n.checked_div(r.b)
+                                .ok_or_else(|| "division by zero".to_string())
+                        };
+
+                        (divide(l.a)?, divide(l.b)?)
+                    },
+                    _ => return Err(format!("`{}` is not linear in `{}`", self, variable)),
+                };
+
+                Ok(Linear { variable: variable.to_string(), a, b })
+            },
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(n) => write!(f, "{}", n),
+            Expr::Variable(name) => write!(f, "{}", name),
+            Expr::Binary(operator, left, right) => {
+                write!(f, "({} {} {})", left, operator.symbol(), right)
+            },
+        }
+    }
+}
+
+// `a * variable + b`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Linear {
+    pub variable: String,
+    pub a: Rational,
+    pub b: Rational,
+}
+
+impl Linear {
+    fn constant(b: Rational, variable: &str) -> Self {
+        Self { variable: variable.to_string(), a: Rational::ZERO, b }
+    }
+}
+
+impl fmt::Display for Linear {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.a.is_zero(), self.b.is_zero()) {
+            (true, _) => write!(f, "{}", self.b),
+            (false, true) => write!(f, "{} * {}", self.a, self.variable),
+            (false, false) if self.b.numerator() < 0 => {
+                write!(f, "{} * {} - {}", self.a, self.variable, -self.b)
+            },
+            (false, false) => write!(f, "{} * {} + {}", self.a, self.variable, self.b),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Roots {
+    One(Rational),
+    None,
+    Infinite,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Equation {
+    pub left: Expr,
+    pub right: Expr,
+}
+
+impl Equation {
+    // `left - right` as a linear expression, to be read as `... = 0`
+    pub fn simplify(&self, variable: &str) -> Result<Linear, String> {
+        let left = self.left.linear(variable)?;
+        let right = self.right.linear(variable)?;
+
+        Ok(Linear {
+            variable: variable.to_string(),
+            a: left.a - right.a,
+            b: left.b - right.b,
+        })
+    }
+
+    pub fn solve(&self, variable: &str) -> Result<Roots, String> {
+        let Linear { a, b, .. } = self.simplify(variable)?;
+
+        Ok(match (a.is_zero(), b.is_zero()) {
+            (false, _) => Roots::One(-b / a),
+            (true, true) => Roots::Infinite,
+            (true, false) => Roots::None,
+        })
+    }
+}
+
+impl fmt::Display for Equation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}", self.left, self.right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::{Equation, Expr, Operator, Rational, Roots};
+
+    fn x() -> Expr {
+        Expr::Variable("x".to_string())
+    }
+
+    #[test]
+    fn test_rational() {
+        assert_eq!(Rational::new(2, -4), Rational::new(-1, 2));
+        assert_eq!(Rational::new(1, 3) + Rational::new(1, 6), Rational::new(1, 2));
+        assert_eq!(Rational::new(3, 4).to_string(), "3/4");
+        assert_eq!(Rational::from(3).checked_div(Rational::ZERO), None);
+    }
+
+    #[test]
+    fn test_solve() {
+        // (4 - x) / 3 = x / 2
+        let equation = Equation {
+            left: Expr::binary(
+                Operator::Div,
+                Expr::binary(Operator::Sub, Expr::Number(4), x()),
+                Expr::Number(3)
+            ),
+            right: Expr::binary(Operator::Div, x(), Expr::Number(2)),
+        };
+
+        assert_eq!(equation.to_string(), "((4 - x) / 3) = (x / 2)");
+        assert_eq!(equation.simplify("x").unwrap().to_string(), "-5/6 * x + 4/3");
+        assert_eq!(equation.solve("x"), Ok(Roots::One(Rational::new(8, 5))));
+
+        let same = Equation { left: x(), right: x() };
+        assert_eq!(same.solve("x"), Ok(Roots::Infinite));
+
+        let never = Equation {
+            left: x(),
+            right: Expr::binary(Operator::Add, x(), Expr::Number(1)),
+        };
+        assert_eq!(never.solve("x"), Ok(Roots::None));
+
+        let square = Equation {
+            left: Expr::binary(Operator::Mul, x(), x()),
+            right: x(),
+        };
+        assert!(square.solve("x").is_err());
+        assert!(square.left.eval().is_err());
+    }
+}
@@ -0,0 +1,342 @@
+// Dense and sparse 2D grids for the puzzles played out on character maps.
+// Coordinates are (x, y) with y growing downwards, as in the puzzle inputs.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::slice::Chunks;
+
+pub const NEIGHBOURS4: [(i64, i64); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+pub const NEIGHBOURS8: [(i64, i64); 8] = [
+    (-1, -1), (0, -1), (1, -1),
+    (-1, 0), (1, 0),
+    (-1, 1), (0, 1), (1, 1),
+];
+
+pub fn neighbours4(pos: (i64, i64)) -> impl Iterator<Item = (i64, i64)> {
+    NEIGHBOURS4.iter().map(move |d| (pos.0 + d.0, pos.1 + d.1))
+}
+
+pub fn neighbours8(pos: (i64, i64)) -> impl Iterator<Item = (i64, i64)> {
+    NEIGHBOURS8.iter().map(move |d| (pos.0 + d.0, pos.1 + d.1))
+}
+
+// how a cell is rendered when printing a grid
+pub trait Tile {
+    fn to_char(&self) -> char;
+}
+
+impl Tile for u8 {
+    fn to_char(&self) -> char {
+        *self as char
+    }
+}
+
+impl Tile for bool {
+    fn to_char(&self) -> char {
+        if *self { '#' } else { '.' }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self {
+        Self { width, height, cells: vec![fill; width * height] }
+    }
+
+    // parse a character map, padding short rows with `pad`
+    pub fn parse_with<F>(input: &str, pad: T, tile: F) -> Self
+    where F: Fn(u8) -> T {
+        let lines: Vec<&str> = input.lines().collect();
+
+        let width = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+        let height = lines.len();
+        let mut cells = Vec::with_capacity(width * height);
+
+        for line in lines {
+            cells.extend(line.bytes().map(&tile));
+            cells.resize(cells.len() + width - line.len(), pad.clone());
+        }
+
+        Self { width, height, cells }
+    }
+
+    pub fn push_row(&mut self, fill: T) {
+        self.cells.resize(self.cells.len() + self.width, fill);
+        self.height += 1;
+    }
+
+    // adds `left` and `right` columns on either side of every row
+    pub fn widen(&mut self, left: usize, right: usize, fill: T) {
+        let width = left + self.width + right;
+        let mut cells = Vec::with_capacity(width * self.height);
+
+        for y in 0..self.height {
+            cells.extend(std::iter::repeat_n(fill.clone(), left));
+            cells.extend_from_slice(self.row(y));
+            cells.extend(std::iter::repeat_n(fill.clone(), right));
+        }
+
+        self.cells = cells;
+        self.width = width;
+    }
+}
+
+impl Grid<u8> {
+    pub fn parse(input: &str) -> Self {
+        Self::parse_with(input, b' ', |b| b)
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, pos: (i64, i64)) -> bool {
+        pos.0 >= 0
+            && pos.1 >= 0
+            && (pos.0 as usize) < self.width
+            && (pos.1 as usize) < self.height
+    }
+
+    pub fn get(&self, pos: (i64, i64)) -> Option<&T> {
+        if self.contains(pos) {
+            Some(&self[(pos.0 as usize, pos.1 as usize)])
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> &mut [T] {
+        &mut self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> Chunks<'_, T> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    pub fn position<P>(&self, predicate: P) -> Option<(usize, usize)>
+    where P: Fn(&T) -> bool {
+        self.positions().find(|&pos| predicate(&self[pos]))
+    }
+
+    pub fn neighbours4(
+        &self,
+        pos: (usize, usize)
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.in_bounds(neighbours4((pos.0 as i64, pos.1 as i64)))
+    }
+
+    pub fn neighbours8(
+        &self,
+        pos: (usize, usize)
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.in_bounds(neighbours8((pos.0 as i64, pos.1 as i64)))
+    }
+
+    // positions from `pos` (exclusive) in direction `d` up to the edge
+    pub fn ray(
+        &self,
+        pos: (usize, usize),
+        d: (i64, i64)
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let mut cur = (pos.0 as i64, pos.1 as i64);
+
+        std::iter::from_fn(move || {
+            cur = (cur.0 + d.0, cur.1 + d.1);
+
+            if self.contains(cur) {
+                Some((cur.0 as usize, cur.1 as usize))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn in_bounds<'a, I>(
+        &'a self,
+        positions: I
+    ) -> impl Iterator<Item = (usize, usize)> + 'a
+    where I: Iterator<Item = (i64, i64)> + 'a {
+        positions
+            .filter(|&pos| self.contains(pos))
+            .map(|pos| (pos.0 as usize, pos.1 as usize))
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: (usize, usize)) -> &T {
+        assert!(pos.0 < self.width && pos.1 < self.height);
+        &self.cells[pos.1 * self.width + pos.0]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, pos: (usize, usize)) -> &mut T {
+        assert!(pos.0 < self.width && pos.1 < self.height);
+        &mut self.cells[pos.1 * self.width + pos.0]
+    }
+}
+
+impl<T: Tile> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for tile in row {
+                write!(f, "{}", tile.to_char())?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+// unbounded grid of occupied positions
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SparseGrid {
+    cells: HashSet<(i64, i64)>,
+}
+
+impl SparseGrid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // positions of every `occupied` character in a character map
+    pub fn parse(input: &str, occupied: char) -> Self {
+        let cells = input
+            .lines()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row
+                    .chars()
+                    .enumerate()
+                    .filter(move |&(_, c)| c == occupied)
+                    .map(move |(x, _)| (x as i64, y as i64))
+            })
+            .collect();
+
+        Self { cells }
+    }
+
+    pub fn contains(&self, pos: (i64, i64)) -> bool {
+        self.cells.contains(&pos)
+    }
+
+    pub fn insert(&mut self, pos: (i64, i64)) -> bool {
+        self.cells.insert(pos)
+    }
+
+    pub fn remove(&mut self, pos: (i64, i64)) -> bool {
+        self.cells.remove(&pos)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.cells.iter().copied()
+    }
+
+    // smallest rectangle containing all positions, as (min, max) inclusive
+    pub fn bounds(&self) -> Option<((i64, i64), (i64, i64))> {
+        let min_x = self.iter().map(|pos| pos.0).min()?;
+        let max_x = self.iter().map(|pos| pos.0).max()?;
+        let min_y = self.iter().map(|pos| pos.1).min()?;
+        let max_y = self.iter().map(|pos| pos.1).max()?;
+
+        Some(((min_x, min_y), (max_x, max_y)))
+    }
+
+    pub fn has_neighbours8(&self, pos: (i64, i64)) -> bool {
+        neighbours8(pos).any(|neighbour| self.contains(neighbour))
+    }
+}
+
+impl FromIterator<(i64, i64)> for SparseGrid {
+    fn from_iter<I: IntoIterator<Item = (i64, i64)>>(iter: I) -> Self {
+        Self { cells: iter.into_iter().collect() }
+    }
+}
+
+impl fmt::Display for SparseGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((min, max)) = self.bounds() {
+            for y in min.1..=max.1 {
+                for x in min.0..=max.0 {
+                    write!(f, "{}", self.contains((x, y)).to_char())?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::{Grid, SparseGrid};
+
+    #[test]
+    fn test_grid() {
+        let grid = Grid::parse("#.#\n..\n");
+
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(2, 1)], b' ');
+        assert_eq!(grid.get((-1, 0)), None);
+        assert_eq!(grid.position(|&b| b == b'#'), Some((0, 0)));
+        assert_eq!(grid.neighbours4((0, 0)).collect::<Vec<_>>(), [(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbours8((1, 1)).count(), 5);
+        assert_eq!(grid.ray((0, 1), (1, 0)).collect::<Vec<_>>(), [(1, 1), (2, 1)]);
+        assert_eq!(grid.to_string(), "#.#\n.. \n");
+    }
+
+    #[test]
+    fn test_widen() {
+        let mut grid = Grid::parse("ab\ncd\n");
+        grid.widen(1, 2, b'.');
+
+        assert_eq!((grid.width(), grid.height()), (5, 2));
+        assert_eq!(grid.to_string(), ".ab..\n.cd..\n");
+    }
+
+    #[test]
+    fn test_sparse_grid() {
+        let grid = SparseGrid::parse("..#\n#..\n", '#');
+
+        assert_eq!(grid.len(), 2);
+        assert_eq!(grid.bounds(), Some(((0, 0), (2, 1))));
+        assert!(!grid.has_neighbours8((2, 0)));
+        assert_eq!(grid.to_string(), "..#\n#..\n");
+    }
+}
@@ -0,0 +1,128 @@
+// Puzzle input providers. Inputs are looked up in local files first so that
+// solutions can run without a session cookie or network access.
+
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+pub trait InputSource {
+    fn get(&self, day: u8) -> io::Result<String>;
+}
+
+// reads `<dir>/dayNN.txt`
+pub struct LocalFiles {
+    dir: PathBuf,
+}
+
+impl LocalFiles {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn path(&self, day: u8) -> PathBuf {
+        self.dir.join(format!("day{:02}.txt", day))
+    }
+}
+
+impl InputSource for LocalFiles {
+    fn get(&self, day: u8) -> io::Result<String> {
+        fs::read_to_string(self.path(day))
+    }
+}
+
+// reads a single file regardless of the day requested
+pub struct File {
+    path: PathBuf,
+}
+
+impl File {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl InputSource for File {
+    fn get(&self, _day: u8) -> io::Result<String> {
+        fs::read_to_string(&self.path)
+    }
+}
+
+// reads all of stdin, so only suitable for running a single day
+pub struct Stdin;
+
+impl InputSource for Stdin {
+    fn get(&self, _day: u8) -> io::Result<String> {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+
+        Ok(input)
+    }
+}
+
+// tries each source in turn, returning the first input found
+pub struct Chain {
+    sources: Vec<Box<dyn InputSource>>,
+}
+
+impl Chain {
+    pub fn new(sources: Vec<Box<dyn InputSource>>) -> Self {
+        Self { sources }
+    }
+
+    // adds a source to try after the others
+    pub fn push(&mut self, source: Box<dyn InputSource>) {
+        self.sources.push(source);
+    }
+}
+
+impl InputSource for Chain {
+    fn get(&self, day: u8) -> io::Result<String> {
+        let mut last_err = io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no input source for day {}", day)
+        );
+
+        for source in &self.sources {
+            match source.get(day) {
+                Ok(input) => return Ok(input),
+                Err(err) => last_err = err,
+            }
+        }
+
+        Err(last_err)
+    }
+}
+
+// Local files in `$AOC_INPUTS` (default `inputs`). The runner in `online/`
+// adds downloads on top of this.
+pub fn default_source() -> Chain {
+    let dir = env::var("AOC_INPUTS").unwrap_or_else(|_| "inputs".to_string());
+
+    Chain::new(vec![Box::new(LocalFiles::new(dir))])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use crate::input::{Chain, InputSource, LocalFiles};
+
+    #[test]
+    fn test_local_files() {
+        let dir = env::temp_dir().join("aoc2022-input-test");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("day03.txt"), "vJrwpWtwJgWrhcsFMMfFFhFp\n").unwrap();
+
+        let source = Chain::new(vec![
+            Box::new(LocalFiles::new(dir.join("missing"))),
+            Box::new(LocalFiles::new(&dir)),
+        ]);
+
+        assert_eq!(source.get(3).unwrap(), "vJrwpWtwJgWrhcsFMMfFFhFp\n");
+        assert!(source.get(4).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+pub mod cli;
+pub mod cycle;
 pub mod day00;
 pub mod day01;
 pub mod day02;
@@ -26,4 +28,236 @@ pub mod day22;
 pub mod day23;
 pub mod day24;
 pub mod day25;
+pub mod error;
+pub mod expression;
+pub mod grid;
+pub mod input;
+pub mod params;
+pub mod pathfinding;
 
+use std::fmt;
+
+use crate::error::Error;
+use crate::params::Overrides;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Signed(i64),
+    Unsigned(u64),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Signed(n) => write!(f, "{}", n),
+            Answer::Unsigned(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(n: i64) -> Self {
+        Answer::Signed(n)
+    }
+}
+
+impl From<u64> for Answer {
+    fn from(n: u64) -> Self {
+        Answer::Unsigned(n)
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Self {
+        Answer::Unsigned(n as u64)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+pub trait Solution {
+    fn day(&self) -> u8;
+    fn try_part1(&self, input: &str) -> Result<Answer, Error>;
+    /// Returns `Ok(None)` for puzzles without a second part.
+    fn try_part2(&self, input: &str) -> Result<Option<Answer>, Error>;
+    fn has_part2(&self) -> bool;
+    /// Like `try_part1`/`try_part2`, with the day's parameters overridden.
+    /// Days without parameters reject any override.
+    fn try_part_with(
+        &self,
+        part: u8,
+        input: &str,
+        overrides: &Overrides
+    ) -> Result<Option<Answer>, Error>;
+
+    fn part1(&self, input: &str) -> Answer {
+        self.try_part1(input).unwrap()
+    }
+
+    fn part2(&self, input: &str) -> Option<Answer> {
+        self.try_part2(input).unwrap()
+    }
+}
+
+type Part = fn(&str) -> Result<Answer, Error>;
+type PartWith = fn(u8, &str, &Overrides) -> Result<Answer, Error>;
+
+pub struct Day {
+    day: u8,
+    part1: Part,
+    part2: Option<Part>,
+    with: Option<PartWith>,
+}
+
+impl Solution for Day {
+    fn day(&self) -> u8 {
+        self.day
+    }
+
+    fn try_part1(&self, input: &str) -> Result<Answer, Error> {
+        (self.part1)(input)
+    }
+
+    fn try_part2(&self, input: &str) -> Result<Option<Answer>, Error> {
+        self.part2.map(|part2| part2(input)).transpose()
+    }
+
+    fn has_part2(&self) -> bool {
+        self.part2.is_some()
+    }
+
+    fn try_part_with(
+        &self,
+        part: u8,
+        input: &str,
+        overrides: &Overrides
+    ) -> Result<Option<Answer>, Error> {
+        if part == 2 && !self.has_part2() {
+            return Ok(None);
+        }
+
+        match (self.with, overrides.first()) {
+            (Some(with), _) => with(part, input, overrides).map(Some),
+            (None, Some((name, _))) => {
+                Err(Error::param(self.day, name, "day has no parameters"))
+            },
+            (None, None) if part == 1 => self.try_part1(input).map(Some),
+            (None, None) => self.try_part2(input),
+        }
+    }
+}
+
+// `try` marks days with fallible `try_part1`/`try_part2` entry points,
+// `params` days that also have `try_part1_with`/`try_part2_with` taking the
+// module's `Params`, optionally followed by how to turn part 2's result into
+// an answer
+macro_rules! day {
+    ($day:expr, try $module:ident) => {
+        Day {
+            day: $day,
+            part1: |input| $module::try_part1(input).map(Answer::from),
+            part2: Some(|input| $module::try_part2(input).map(Answer::from)),
+            with: None,
+        }
+    };
+    ($day:expr, try $module:ident, params) => {
+        day!($day, try $module, params, Answer::from)
+    };
+    ($day:expr, try $module:ident, params, $answer2:expr) => {
+        Day {
+            day: $day,
+            part1: |input| $module::try_part1(input).map(Answer::from),
+            part2: Some(|input| $module::try_part2(input).map($answer2)),
+            with: Some(|part, input, overrides| {
+                let params = <$module::Params as crate::params::Params>
+                    ::from_overrides($day, overrides)?;
+
+                match part {
+                    1 => $module::try_part1_with(input, &params).map(Answer::from),
+                    _ => $module::try_part2_with(input, &params).map($answer2),
+                }
+            }),
+        }
+    };
+    ($day:expr, try $module:ident, part1) => {
+        Day {
+            day: $day,
+            part1: |input| $module::try_part1(input).map(Answer::from),
+            part2: None,
+            with: None,
+        }
+    };
+}
+
+pub static DAYS: [Day; 25] = [
+    day!(1, try day01),
+    day!(2, try day02),
+    day!(3, try day03),
+    day!(4, try day04),
+    day!(5, try day05),
+    day!(6, try day06),
+    // the puzzle only asks for the size of the directory to delete
+    day!(7, try day07, params, |deletion| deletion.size.into()),
+    day!(8, try day08),
+    day!(9, try day09),
+    day!(10, try day10, params),
+    day!(11, try day11, params),
+    day!(12, try day12),
+    day!(13, try day13, params),
+    day!(14, try day14),
+    day!(15, try day15, params),
+    day!(16, try day16),
+    day!(17, try day17),
+    day!(18, try day18),
+    day!(19, try day19),
+    day!(20, try day20),
+    day!(21, try day21, params),
+    day!(22, try day22),
+    day!(23, try day23),
+    day!(24, try day24),
+    day!(25, try day25, part1),
+];
+
+pub fn solution(day: u8) -> Option<&'static dyn Solution> {
+    DAYS
+        .iter()
+        .find(|solution| solution.day == day)
+        .map(|solution| solution as &dyn Solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{solution, Answer};
+
+    #[test]
+    fn test_registry() {
+        assert!(solution(0).is_none());
+        assert!(solution(26).is_none());
+        assert!((1..=24).all(|day| solution(day).is_some()));
+
+        let day06 = solution(6).unwrap();
+        assert_eq!(day06.part1("mjqjpqmgbljsphdztnvjfqwrcgsmlb"), Answer::Unsigned(7));
+        assert_eq!(day06.part2("mjqjpqmgbljsphdztnvjfqwrcgsmlb"), Some(Answer::Unsigned(19)));
+
+        assert!(!solution(25).unwrap().has_part2());
+        assert_eq!(solution(25).unwrap().part2("1=\n"), None);
+
+        assert!(solution(25).unwrap().try_part1("3\n").is_err());
+    }
+
+    #[test]
+    fn test_overrides() {
+        let input = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+        let overrides = [("preset".to_string(), "example".to_string())];
+        let day06 = solution(6).unwrap();
+
+        assert_eq!(day06.try_part_with(1, input, &[]), Ok(Some(Answer::Unsigned(7))));
+        assert!(day06.try_part_with(1, input, &overrides).is_err());
+    }
+}
@@ -0,0 +1,53 @@
+// Puzzle parameters that the input doesn't carry, such as the row day 15
+// scans, which differ between the examples and the actual puzzles. The runner
+// passes overrides as `name=value` pairs.
+
+use std::str::FromStr;
+
+use crate::error::Error;
+
+pub type Overrides = [(String, String)];
+
+pub trait Params: Default {
+    // the values used by the puzzle's worked example
+    fn example() -> Self;
+
+    // `Err` describes the expected value
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String>;
+
+    // `preset=example` starts from the example's values, later overrides
+    // take precedence over earlier ones
+    fn from_overrides(day: u8, overrides: &Overrides) -> Result<Self, Error> {
+        let mut params = Self::default();
+
+        for (name, value) in overrides {
+            match (name.as_str(), value.as_str()) {
+                ("preset", "example") => params = Self::example(),
+                ("preset", "puzzle") => params = Self::default(),
+                ("preset", _) => {
+                    return Err(Error::param(day, name, "expected `example` or `puzzle`"));
+                },
+                _ => params
+                    .set(name, value)
+                    .map_err(|expected| {
+                        Error::param(day, name, &format!("expected {}", expected))
+                    })?,
+            }
+        }
+
+        Ok(params)
+    }
+}
+
+// parse `value`, describing it as `expected` on failure
+pub fn value<T: FromStr>(value: &str, expected: &str) -> Result<T, String> {
+    value.parse().map_err(|_| expected.to_string())
+}
+
+// split a `name=value` argument
+pub fn parse_override(arg: &str) -> Option<(String, String)> {
+    arg
+        .split_once('=')
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+}
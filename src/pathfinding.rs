@@ -0,0 +1,240 @@
+// Graph searches over a caller-supplied neighbour function. Nodes are
+// discovered lazily, so implicit graphs such as (position, minute) states
+// work as well as explicit ones.
+
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<N> {
+    // from start to goal, both inclusive
+    pub nodes: Vec<N>,
+    pub cost: usize,
+}
+
+impl<N> Path<N> {
+    pub fn start(&self) -> &N {
+        self.nodes.first().unwrap()
+    }
+
+    pub fn goal(&self) -> &N {
+        self.nodes.last().unwrap()
+    }
+}
+
+// nodes seen so far, with the index of the node they were reached from
+struct Visited<N> {
+    nodes: Vec<N>,
+    parents: Vec<usize>,
+    costs: Vec<usize>,
+    index: HashMap<N, usize>,
+}
+
+impl<N: Clone + Eq + Hash> Visited<N> {
+    fn new(start: N) -> Self {
+        Self {
+            nodes: vec![start.clone()],
+            parents: vec![0],
+            costs: vec![0],
+            index: HashMap::from([(start, 0)]),
+        }
+    }
+
+    // returns the node's index if it is new or reached more cheaply
+    fn visit(&mut self, node: N, parent: usize, cost: usize) -> Option<usize> {
+        match self.index.entry(node) {
+            Entry::Occupied(e) => {
+                let i = *e.get();
+
+                if cost < self.costs[i] {
+                    self.parents[i] = parent;
+                    self.costs[i] = cost;
+                    Some(i)
+                } else {
+                    None
+                }
+            },
+            Entry::Vacant(e) => {
+                let i = self.nodes.len();
+
+                self.nodes.push(e.key().clone());
+                self.parents.push(parent);
+                self.costs.push(cost);
+                e.insert(i);
+
+                Some(i)
+            },
+        }
+    }
+
+    fn path(&self, mut i: usize) -> Path<N> {
+        let cost = self.costs[i];
+        let mut nodes = vec![self.nodes[i].clone()];
+
+        while i != 0 {
+            i = self.parents[i];
+            nodes.push(self.nodes[i].clone());
+        }
+
+        nodes.reverse();
+
+        Path { nodes, cost }
+    }
+}
+
+// shortest path by number of steps
+pub fn bfs<N, F, I, G>(start: N, mut neighbours: F, mut is_goal: G) -> Option<Path<N>>
+where
+    N: Clone + Eq + Hash,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = N>,
+    G: FnMut(&N) -> bool,
+{
+    let mut visited = Visited::new(start);
+    let mut queue = VecDeque::from([0]);
+
+    while let Some(i) = queue.pop_front() {
+        if is_goal(&visited.nodes[i]) {
+            return Some(visited.path(i));
+        }
+
+        let cost = visited.costs[i] + 1;
+
+        // nodes are first reached along a shortest path
+        for next in neighbours(&visited.nodes[i]) {
+            if let Some(j) = visited.visit(next, i, cost) {
+                queue.push_back(j);
+            }
+        }
+    }
+
+    None
+}
+
+// number of steps to every node reachable from `start`
+pub fn bfs_reach<N, F, I>(start: N, mut neighbours: F) -> HashMap<N, usize>
+where
+    N: Clone + Eq + Hash,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = N>,
+{
+    let mut visited = Visited::new(start);
+    let mut queue = VecDeque::from([0]);
+
+    while let Some(i) = queue.pop_front() {
+        let cost = visited.costs[i] + 1;
+
+        // nodes are first reached along a shortest path
+        for next in neighbours(&visited.nodes[i]) {
+            if let Some(j) = visited.visit(next, i, cost) {
+                queue.push_back(j);
+            }
+        }
+    }
+
+    visited
+        .nodes
+        .into_iter()
+        .zip(visited.costs)
+        .collect()
+}
+
+// shortest path with weighted edges, given as (node, cost) pairs
+pub fn dijkstra<N, F, I, G>(start: N, neighbours: F, is_goal: G) -> Option<Path<N>>
+where
+    N: Clone + Eq + Hash,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = (N, usize)>,
+    G: FnMut(&N) -> bool,
+{
+    astar(start, neighbours, |_| 0, is_goal)
+}
+
+// shortest path with weighted edges, guided by a heuristic that must never
+// overestimate the remaining cost
+pub fn astar<N, F, I, H, G>(
+    start: N,
+    mut neighbours: F,
+    mut heuristic: H,
+    mut is_goal: G
+) -> Option<Path<N>>
+where
+    N: Clone + Eq + Hash,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = (N, usize)>,
+    H: FnMut(&N) -> usize,
+    G: FnMut(&N) -> bool,
+{
+    let mut heap = BinaryHeap::from([Reverse((heuristic(&start), 0, 0))]);
+    let mut visited = Visited::new(start);
+
+    while let Some(Reverse((_, cost, i))) = heap.pop() {
+        // skip entries superseded by a cheaper route
+        if cost > visited.costs[i] {
+            continue;
+        }
+
+        if is_goal(&visited.nodes[i]) {
+            return Some(visited.path(i));
+        }
+
+        for (next, step) in neighbours(&visited.nodes[i]) {
+            let next_cost = cost + step;
+            let estimate = next_cost + heuristic(&next);
+
+            if let Some(j) = visited.visit(next, i, next_cost) {
+                heap.push(Reverse((estimate, next_cost, j)));
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pathfinding::{astar, bfs, bfs_reach, dijkstra};
+
+    // 0 - 1 - 2 - 3, with a costly shortcut 0 - 3
+    fn edges(node: &u8) -> Vec<(u8, usize)> {
+        match node {
+            0 => vec![(1, 1), (3, 5)],
+            1 => vec![(0, 1), (2, 1)],
+            2 => vec![(1, 1), (3, 1)],
+            _ => vec![(2, 1), (0, 5)],
+        }
+    }
+
+    fn unweighted(node: &u8) -> Vec<u8> {
+        edges(node).into_iter().map(|(next, _)| next).collect()
+    }
+
+    #[test]
+    fn test_bfs() {
+        let path = bfs(0, unweighted, |&node| node == 3).unwrap();
+
+        assert_eq!(path.nodes, [0, 3]);
+        assert_eq!(path.cost, 1);
+        assert_eq!(bfs(0, unweighted, |&node| node == 4), None);
+        assert_eq!(bfs_reach(0, unweighted).get(&2), Some(&2));
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        let path = dijkstra(0, edges, |&node| node == 3).unwrap();
+
+        assert_eq!(path.nodes, [0, 1, 2, 3]);
+        assert_eq!(path.cost, 3);
+    }
+
+    #[test]
+    fn test_astar() {
+        let heuristic = |&node: &u8| 3 - node as usize;
+        let path = astar(0, edges, heuristic, |&node| node == 3).unwrap();
+
+        assert_eq!(path.nodes, [0, 1, 2, 3]);
+        assert_eq!(path.cost, 3);
+    }
+}